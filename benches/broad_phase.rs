@@ -0,0 +1,101 @@
+//! Compares the broad phase used by `collect_collision_pairs` against the
+//! brute force pair test it replaced, on a 2-1-sized level (212x15 tiles)
+//! with a few hundred bodies.
+//!
+//! Run with `cargo bench --bench broad_phase`.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use bevy::prelude::*;
+use mario::{
+    config::TILE_SIZE,
+    game::physics::{Aabb, SpatialHash},
+};
+
+const LEVEL_SIZE: Vec2 = Vec2::new(212. * TILE_SIZE as f32, 15. * TILE_SIZE as f32);
+const ITERATIONS: u32 = 1000;
+
+/// Deterministic bodies spread over the level, mostly 16x16 with some 16x32.
+fn bodies(count: u32) -> Vec<(Entity, Aabb)> {
+    let mut seed: u32 = 0x2545_f491;
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        seed as f32 / u32::MAX as f32
+    };
+
+    (0..count)
+        .map(|index| {
+            let size = if index % 8 == 0 {
+                Vec2::new(16., 32.)
+            } else {
+                Vec2::splat(16.)
+            };
+            let point = Vec2::new(next(), next()) * (LEVEL_SIZE - size);
+            (Entity::from_raw(index), Aabb::from_vec_size(point, size))
+        })
+        .collect()
+}
+
+fn brute_force(bodies: &[(Entity, Aabb)], pairs: &mut Vec<(Entity, Entity)>) {
+    for (entity_a, aabb_a) in bodies {
+        for (entity_b, aabb_b) in bodies {
+            if entity_a <= entity_b {
+                continue;
+            }
+            if aabb_a.intersects(aabb_b) {
+                pairs.push((*entity_a, *entity_b));
+            }
+        }
+    }
+}
+
+fn spatial_hash(
+    bodies: &[(Entity, Aabb)],
+    spatial_hash: &mut SpatialHash,
+    pairs: &mut Vec<(Entity, Entity)>,
+) {
+    spatial_hash.clear();
+    for (entity, aabb) in bodies {
+        spatial_hash.insert(*entity, *aabb);
+    }
+    spatial_hash.collect_pairs(pairs);
+}
+
+fn measure(mut f: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    for count in [100, 300, 600] {
+        let bodies = bodies(count);
+        let mut pairs = Vec::new();
+        let mut grid = SpatialHash::default();
+
+        let brute = measure(|| {
+            pairs.clear();
+            brute_force(black_box(&bodies), &mut pairs);
+            black_box(&pairs);
+        });
+        let expected = pairs.len();
+
+        let hashed = measure(|| {
+            pairs.clear();
+            spatial_hash(black_box(&bodies), &mut grid, &mut pairs);
+            black_box(&pairs);
+        });
+        assert_eq!(expected, pairs.len());
+
+        println!(
+            "{count:>4} bodies, {expected:>4} pairs: brute force {brute:>10.2?}, spatial hash {hashed:>10.2?}"
+        );
+    }
+}
@@ -1,9 +1,18 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
 use bevy::prelude::*;
 
-use crate::game::physics::DT;
-use crate::game::SettingsState;
+use crate::game::movement::InputRecorder;
+use crate::game::{GameState, SettingsState};
 use crate::AppSet;
 
+/// Where recorded input tapes are saved, next to the ones tests replay.
+const INPUT_TAPE_DIR: &str = "tests/tapes";
+
 #[derive(Debug, Default, PartialEq, Resource)]
 pub struct DebugMode(pub bool);
 
@@ -12,7 +21,7 @@ pub(super) fn plugin(app: &mut App) {
 
     app.add_systems(
         Update,
-        (toggle_fullscreen, toogle_debug).in_set(AppSet::RecordInput),
+        (toggle_fullscreen, toogle_debug, toggle_input_recording).in_set(AppSet::RecordInput),
     );
 
     app.add_systems(
@@ -48,7 +57,45 @@ fn toogle_debug(
     }
 }
 
+fn toggle_input_recording(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    recorder: Option<Res<InputRecorder>>,
+    gs: Res<GameState>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F9) {
+        return;
+    }
+    match recorder {
+        Some(recorder) => {
+            let path = input_tape_path(gs.current_level.name());
+            let saved = fs::create_dir_all(INPUT_TAPE_DIR).and_then(|()| recorder.0.save(&path));
+            match saved {
+                Ok(()) => info!(
+                    "saved {} input frames to {}",
+                    recorder.0.frames.len(),
+                    path.display()
+                ),
+                Err(err) => error!("failed to save input tape to {}: {}", path.display(), err),
+            }
+            commands.remove_resource::<InputRecorder>();
+        }
+        None => commands.init_resource::<InputRecorder>(),
+    }
+}
+
+/// A new tape file for a recording on `level`, named after the level and the
+/// time, e.g. `tests/tapes/1-1-1760781600.tape.json`.
+fn input_tape_path(level: &str) -> PathBuf {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    Path::new(INPUT_TAPE_DIR).join(format!("{}-{}.tape.json", level, secs))
+}
+
 fn move_camera(
+    // Virtual time is paused while debugging.
+    time: Res<Time<Real>>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
     mut query: Query<(&mut Transform, &mut OrthographicProjection), With<Camera>>,
 ) {
@@ -83,7 +130,7 @@ fn move_camera(
         }
 
         let z = transform.translation.z;
-        transform.translation += direction * 500. * DT;
+        transform.translation += direction * 500. * time.delta_seconds();
         transform.translation.z = z;
     }
 }
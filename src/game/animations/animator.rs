@@ -0,0 +1,154 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, utils::HashMap};
+
+use super::Animate;
+use crate::game::assets::AnimationClip;
+
+/// Shortest time a frame stays on screen, for clips without a frame length.
+const MIN_FRAME_DURATION: Duration = Duration::from_millis(1);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+pub enum PlayMode {
+    #[default]
+    Loop,
+    /// Stops on the last frame and sends [`AnimationFinished`].
+    Once,
+}
+
+/// Sent when a clip played with [`Animator::play_once`] reaches its end.
+#[derive(Event, Debug, Clone)]
+pub struct AnimationFinished {
+    pub entity: Entity,
+    pub clip: String,
+}
+
+/// Plays the named clips of a sprite sheet on the entity's [`TextureAtlas`].
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct Animator {
+    clips: HashMap<String, AnimationClip>,
+    clip: String,
+    mode: PlayMode,
+    frame: usize,
+    timer: Timer,
+    finished: bool,
+    just_finished: bool,
+    /// Scales the frame length of the current clip, 2 plays twice as fast.
+    pub speed: f32,
+}
+
+impl Animator {
+    pub fn new(clips: HashMap<String, AnimationClip>, clip: &str) -> Self {
+        let mut animator = Self {
+            clips,
+            clip: String::new(),
+            mode: PlayMode::Loop,
+            frame: 0,
+            timer: Timer::default(),
+            finished: false,
+            just_finished: false,
+            speed: 1.,
+        };
+        animator.start(clip, PlayMode::Loop);
+        animator
+    }
+
+    /// Loops `clip`, unless it is already looping.
+    pub fn play(&mut self, clip: &str) {
+        if self.clip != clip || self.mode != PlayMode::Loop {
+            self.start(clip, PlayMode::Loop);
+        }
+    }
+
+    /// Plays `clip` once, unless it is already playing once.
+    pub fn play_once(&mut self, clip: &str) {
+        if self.clip != clip || self.mode != PlayMode::Once {
+            self.start(clip, PlayMode::Once);
+        }
+    }
+
+    pub fn clip(&self) -> &str {
+        &self.clip
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn atlas_index(&self) -> Option<usize> {
+        self.current()?.frames.get(self.frame).copied()
+    }
+
+    fn current(&self) -> Option<&AnimationClip> {
+        self.clips.get(&self.clip)
+    }
+
+    fn start(&mut self, clip: &str, mode: PlayMode) {
+        // Entities without a sprite sheet have no clips at all.
+        if !self.clips.is_empty() && !self.clips.contains_key(clip) {
+            warn!("no animation clip {}", clip);
+        }
+        self.clip = clip.to_string();
+        self.mode = mode;
+        self.frame = 0;
+        self.finished = false;
+        let frame_len = self.current().map_or(0., |clip| clip.frame_len);
+        self.timer = Timer::new(
+            Duration::from_secs_f32(frame_len.max(0.)).max(MIN_FRAME_DURATION),
+            TimerMode::Repeating,
+        );
+    }
+
+    /// Advances the current clip, returns whether a clip played once just
+    /// reached its end.
+    fn tick(&mut self, delta: Duration) -> bool {
+        let Some(len) = self.current().map(|clip| clip.frames.len()) else {
+            return false;
+        };
+        if self.finished || len == 0 || (len == 1 && self.mode == PlayMode::Loop) {
+            return false;
+        }
+
+        self.timer.tick(delta.mul_f32(self.speed.max(0.)));
+        let next = self.frame + self.timer.times_finished_this_tick() as usize;
+        match self.mode {
+            PlayMode::Loop => self.frame = next % len,
+            PlayMode::Once if next >= len => {
+                self.frame = len - 1;
+                self.finished = true;
+                return true;
+            }
+            PlayMode::Once => self.frame = next,
+        }
+        false
+    }
+}
+
+impl Animate for Animator {
+    fn get_atlas_index(&self) -> usize {
+        self.atlas_index().unwrap_or_default()
+    }
+
+    fn update_timer(&mut self, delta: Duration) {
+        if self.tick(delta) {
+            self.just_finished = true;
+        }
+    }
+}
+
+pub(super) fn send_animation_finished(
+    mut animator_q: Query<(Entity, &mut Animator)>,
+    mut finished_events: EventWriter<AnimationFinished>,
+) {
+    for (entity, mut animator) in &mut animator_q {
+        if !animator.just_finished {
+            continue;
+        }
+        animator.just_finished = false;
+        finished_events.send(AnimationFinished {
+            entity,
+            clip: animator.clip.clone(),
+        });
+    }
+}
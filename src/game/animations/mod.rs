@@ -1,24 +1,22 @@
-pub mod goomba_animation;
-pub mod player_animation;
+pub mod animator;
 
 use std::time::Duration;
 
-use crate::AppSet;
-use bevy::prelude::*;
+use crate::{config::TILE_SIZE, game::physics::BoxCollider, AppSet};
+use bevy::{prelude::*, sprite::Anchor};
 
-use self::goomba_animation::GoombaAnimation;
-use self::player_animation::PlayerAnimation;
+use self::animator::{send_animation_finished, AnimationFinished, Animator};
 
 pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Animator>();
+    app.add_event::<AnimationFinished>();
     app.add_systems(
         Update,
         (
-            update_animation_timer::<PlayerAnimation>.in_set(AppSet::TickTimers),
-            update_animation_timer::<GoombaAnimation>.in_set(AppSet::TickTimers),
-            (update_animation_atlas::<PlayerAnimation>,)
+            (update_animation_timer::<Animator>, send_animation_finished)
                 .chain()
-                .in_set(AppSet::Update),
-            (update_animation_atlas::<GoombaAnimation>,)
+                .in_set(AppSet::TickTimers),
+            (update_animation_atlas::<Animator>, anchor_feet)
                 .chain()
                 .in_set(AppSet::Update),
         ),
@@ -26,7 +24,6 @@ pub(super) fn plugin(app: &mut App) {
 }
 
 pub trait Animate {
-    fn changed(&self) -> bool;
     fn get_atlas_index(&self) -> usize;
     fn update_timer(&mut self, delta: Duration);
 }
@@ -39,8 +36,32 @@ fn update_animation_timer<T: Animate + Component>(time: Res<Time>, mut query: Qu
 
 fn update_animation_atlas<T: Animate + Component>(mut query: Query<(&T, &mut TextureAtlas)>) {
     for (animation, mut atlas) in &mut query {
-        if animation.changed() {
-            atlas.index = animation.get_atlas_index();
+        // Only written when the frame differs, to keep `Changed<TextureAtlas>`
+        // meaningful.
+        let index = animation.get_atlas_index();
+        if atlas.index != index {
+            atlas.index = index;
+        }
+    }
+}
+
+/// Keeps the feet of bodies drawn with frames taller than a tile where a
+/// tile sized frame would put them, so growing doesn't sink into the ground.
+fn anchor_feet(
+    layouts: Res<Assets<TextureAtlasLayout>>,
+    mut query: Query<(&TextureAtlas, &mut Sprite), (With<BoxCollider>, Changed<TextureAtlas>)>,
+) {
+    for (atlas, mut sprite) in &mut query {
+        let Some(rect) = layouts
+            .get(&atlas.layout)
+            .and_then(|layout| layout.textures.get(atlas.index))
+        else {
+            continue;
+        };
+        let height = rect.height() as f32;
+        let anchor = Anchor::Custom(Vec2::new(0., TILE_SIZE as f32 / 2. / height - 0.5));
+        if sprite.anchor != anchor {
+            sprite.anchor = anchor;
         }
     }
 }
@@ -1,20 +1,27 @@
+use std::{cmp::Ordering, fmt};
+
 use bevy::{
+    asset::LoadedFolder,
     prelude::*,
     render::texture::{ImageLoaderSettings, ImageSampler},
-    utils::HashMap,
+    utils::{HashMap, HashSet},
 };
 use bevy_common_assets::json::JsonAssetPlugin;
 use serde::Deserialize;
 
+use crate::{game::tiles::components::TileSet, screen::Screen};
+
 #[derive(Deserialize, Asset, TypePath, Default, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct LevelAsset {
     pub sprite_sheet: String,
     pub pattern_sheet: String,
     pub music_sheet: String,
+    #[serde(default)]
     pub checkpoints: Vec<[u32; 2]>,
     pub layers: Vec<LevelLayerAsset>,
     pub entities: Vec<LevelEntityAsset>,
+    #[serde(default)]
     pub triggers: Vec<LevelTriggerAsset>,
 }
 
@@ -24,7 +31,7 @@ pub struct LevelLayerAsset {
     pub tiles: Vec<LevelTileAsset>,
 }
 
-#[derive(Deserialize, TypePath, Default, Debug)]
+#[derive(Deserialize, TypePath, Default, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct LevelTileAsset {
     pub style: Option<String>,
@@ -36,50 +43,105 @@ pub struct LevelTileAsset {
 #[derive(Deserialize, TypePath, Default, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct LevelEntityAsset {
+    pub id: Option<String>,
     pub name: String,
     pub pos: [u32; 2],
+    #[serde(default)]
+    pub props: LevelEntityPropsAsset,
+}
+
+#[derive(Deserialize, TypePath, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LevelEntityPropsAsset {
+    pub dir: Option<String>,
+    pub goes_to: Option<LevelGoesToAsset>,
+    pub back_to: Option<String>,
+}
+
+#[derive(Deserialize, TypePath, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LevelGoesToAsset {
+    pub name: String,
 }
 
 #[derive(Deserialize, TypePath, Default, Debug)]
 #[serde(rename_all = "camelCase")]
-struct LevelTriggerAsset {
-    action: String,
-    name: String,
-    pos: [u32; 2],
+pub struct LevelTriggerAsset {
+    pub action: String,
+    pub name: String,
+    pub pos: [u32; 2],
 }
 
+/// A sprite sheet, either a grid of `tiles` or a list of `frames` with
+/// their own rect.
 #[derive(Deserialize, Asset, TypePath, Default, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct SpriteAsset {
-    image_url: String,
-    tile_w: u8,
-    tile_h: u8,
-    tiles: Vec<SpriteTileAsset>,
-    animations: Vec<AnimationAsset>,
+    #[serde(alias = "imageURL")]
+    pub image_url: String,
+    #[serde(default)]
+    pub tile_w: u32,
+    #[serde(default)]
+    pub tile_h: u32,
+    #[serde(default)]
+    pub tiles: Vec<SpriteTileAsset>,
+    #[serde(default)]
+    pub frames: Vec<SpriteFrameAsset>,
+    #[serde(default)]
+    pub animations: Vec<AnimationAsset>,
 }
 
 #[derive(Deserialize, TypePath, Default, Debug)]
 #[serde(rename_all = "camelCase")]
-struct SpriteTileAsset {
-    name: String,
-    index: Option<[u8; 2]>,
+pub struct SpriteTileAsset {
+    pub name: String,
+    pub index: Option<[u32; 2]>,
+    /// Default behaviour of the tile when a level places it, tile sheets only.
+    pub behavior: Option<String>,
 }
 
 #[derive(Deserialize, TypePath, Default, Debug)]
 #[serde(rename_all = "camelCase")]
-struct AnimationAsset {
-    name: String,
-    frame_len: f32,
-    frames: Vec<String>,
+pub struct SpriteFrameAsset {
+    pub name: String,
+    pub rect: [u32; 4],
 }
 
-#[derive(Deserialize, Asset, TypePath, Default, Debug)]
+#[derive(Deserialize, TypePath, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AnimationAsset {
+    pub name: String,
+    pub frame_len: f32,
+    pub frames: Vec<String>,
+    /// Default behaviour of the animated tile, tile sheets only.
+    pub behavior: Option<String>,
+}
+
+impl SpriteAsset {
+    /// Named rects of the sheet, in the order of its atlas.
+    fn rects(&self) -> Vec<(&str, URect)> {
+        let tiles = self.tiles.iter().filter_map(|tile| {
+            let [x, y] = tile.index?;
+            let min = UVec2::new(x * self.tile_w, y * self.tile_h);
+            let rect = URect::from_corners(min, min + UVec2::new(self.tile_w, self.tile_h));
+            Some((tile.name.as_ref(), rect))
+        });
+        let frames = self.frames.iter().map(|frame| {
+            let [x, y, w, h] = frame.rect;
+            let rect = URect::new(x, y, x + w, y + h);
+            (frame.name.as_ref(), rect)
+        });
+        tiles.chain(frames).collect()
+    }
+}
+
+#[derive(Deserialize, Asset, TypePath, Default, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct PatternAsset {
     pub patterns: HashMap<String, PatternTilesAsset>,
 }
 
-#[derive(Deserialize, TypePath, Default, Debug)]
+#[derive(Deserialize, TypePath, Default, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct PatternTilesAsset {
     pub tiles: Vec<LevelTileAsset>,
@@ -94,14 +156,17 @@ pub(super) fn plugin(app: &mut App) {
     app.register_type::<HandleMap<TextureKey>>();
     app.init_resource::<HandleMap<TextureKey>>();
 
-    app.register_type::<HandleMap<LevelKey>>();
-    app.init_resource::<HandleMap<LevelKey>>();
+    app.register_type::<Levels>();
+    app.init_resource::<Levels>();
+    app.add_systems(OnExit(Screen::Loading), build_levels);
 
-    // app.register_type::<HandleMap<SpriteKey>>();
-    // app.init_resource::<HandleMap<SpriteKey>>();
+    app.register_type::<SpriteSheets>();
+    app.init_resource::<SpriteSheets>();
+    app.add_systems(OnExit(Screen::Loading), build_sprite_sheets);
 
-    app.register_type::<HandleMap<PatternKey>>();
-    app.init_resource::<HandleMap<PatternKey>>();
+    app.register_type::<PatternSheets>();
+    app.init_resource::<PatternSheets>();
+    app.add_systems(OnExit(Screen::Loading), build_pattern_sheets);
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Reflect)]
@@ -110,6 +175,25 @@ pub enum TextureKey {
     Tiles,
 }
 
+impl TextureKey {
+    /// The texture a sprite sheet's `imageUrl` points at.
+    pub fn from_image_url(url: &str) -> Option<Self> {
+        match url.rsplit('/').next() {
+            Some("sprites.png") => Some(TextureKey::Entities),
+            Some("tiles.png") => Some(TextureKey::Tiles),
+            _ => None,
+        }
+    }
+
+    /// Path of the texture, relative to the assets directory.
+    pub fn path(self) -> &'static str {
+        match self {
+            TextureKey::Entities => "textures/entities.png",
+            TextureKey::Tiles => "textures/tiles.png",
+        }
+    }
+}
+
 impl AssetKey for TextureKey {
     type Asset = Image;
 }
@@ -117,125 +201,463 @@ impl AssetKey for TextureKey {
 impl FromWorld for HandleMap<TextureKey> {
     fn from_world(world: &mut World) -> Self {
         let asset_server = world.resource::<AssetServer>();
-        [
-            (
-                TextureKey::Entities,
-                asset_server.load("textures/entities.png"),
-            ),
-            (TextureKey::Tiles, asset_server.load("textures/tiles.png")),
-        ]
-        .into()
+        [TextureKey::Entities, TextureKey::Tiles]
+            .map(|key| (key, asset_server.load(key.path())))
+            .into()
+    }
+}
+
+/// Name of the level a game starts at, unless another one is given.
+const FIRST_LEVEL: &str = "1-1";
+
+/// A level, by the name used in level files and for its file, e.g. `"1-2"`
+/// for `assets/levels/1-2.level.json`.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Reflect)]
+pub struct LevelKey(String);
+
+impl LevelKey {
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+
+    /// World and level numbers of the levels played in order, named like
+    /// `"1-2"`. Sub-levels and debug levels have none.
+    fn stage(&self) -> Option<(u32, u32)> {
+        let (world, level) = self.0.split_once('-')?;
+        Some((world.parse().ok()?, level.parse().ok()?))
     }
 }
 
-#[derive(Default, Copy, Clone, Eq, PartialEq, Hash, Reflect)]
-pub enum LevelKey {
-    #[default]
-    OneOne,
-    OneTwo,
-    OneThree,
-    OneFour,
-    TwoOne,
+impl Default for LevelKey {
+    fn default() -> Self {
+        Self::from(FIRST_LEVEL)
+    }
+}
+
+impl From<&str> for LevelKey {
+    fn from(name: &str) -> Self {
+        Self(name.to_string())
+    }
 }
 
-impl AssetKey for LevelKey {
-    type Asset = LevelAsset;
+impl fmt::Display for LevelKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Every level of `assets/levels`, keyed by file name without the
+/// `.level.json` extension.
+#[derive(Resource, Default, Reflect)]
+#[reflect(Resource)]
+pub struct Levels {
+    folder: Handle<LoadedFolder>,
+    levels: HashMap<LevelKey, Handle<LevelAsset>>,
+}
+
+impl Levels {
+    pub fn get(&self, key: &LevelKey) -> Option<&Handle<LevelAsset>> {
+        self.levels.get(key)
+    }
+
+    /// Every level, the ones played in order first, then the others by name.
+    pub fn keys(&self) -> Vec<LevelKey> {
+        let mut keys = self.levels.keys().cloned().collect::<Vec<_>>();
+        keys.sort_by(|a, b| match (a.stage(), b.stage()) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => a.0.cmp(&b.0),
+        });
+        keys
+    }
+
+    /// The level played after `key` is completed.
+    pub fn next(&self, key: &LevelKey) -> Option<LevelKey> {
+        let stage = key.stage()?;
+        self.levels
+            .keys()
+            .filter(|other| other.stage().is_some_and(|other| other > stage))
+            .min_by_key(|other| other.stage())
+            .cloned()
+    }
+
+    /// Keeps only the levels for which `f` returns `true`.
+    pub fn retain(&mut self, mut f: impl FnMut(&LevelKey, &Handle<LevelAsset>) -> bool) {
+        self.levels.retain(|key, handle| f(key, handle));
+    }
+
+    pub fn all_loaded(&self, asset_server: &AssetServer) -> bool {
+        asset_server.is_loaded_with_dependencies(&self.folder)
+    }
 }
 
-impl FromWorld for HandleMap<LevelKey> {
+impl FromWorld for Levels {
     fn from_world(world: &mut World) -> Self {
         let asset_server = world.resource::<AssetServer>();
-        [
-            (LevelKey::OneOne, asset_server.load("levels/1-1.level.json")),
-            (LevelKey::OneTwo, asset_server.load("levels/1-2.level.json")),
-            (
-                LevelKey::OneThree,
-                asset_server.load("levels/1-3.level.json"),
-            ),
-            (
-                LevelKey::OneFour,
-                asset_server.load("levels/1-4.level.json"),
-            ),
-            (LevelKey::TwoOne, asset_server.load("levels/2-1.level.json")),
-        ]
-        .into()
+        Self {
+            folder: asset_server.load_folder("levels"),
+            levels: HashMap::new(),
+        }
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Reflect)]
-pub enum PatternKey {
-    Overworld,
-    Underwater,
-    UnderWorld,
-    Castle,
-}
-
-impl From<&str> for PatternKey {
-    fn from(value: &str) -> Self {
-        match value {
-            "overworld.pattern" => PatternKey::Overworld,
-            "castle.pattern" => PatternKey::Castle,
-            "underwater.pattern" => PatternKey::Underwater,
-            "underworld.pattern" => PatternKey::Overworld,
-            _ => PatternKey::Overworld,
+const LEVEL_EXTENSION: &str = ".level.json";
+
+pub(crate) fn build_levels(
+    mut levels: ResMut<Levels>,
+    asset_server: Res<AssetServer>,
+    folders: Res<Assets<LoadedFolder>>,
+) {
+    let Some(folder) = folders.get(&levels.folder) else {
+        return;
+    };
+    levels.levels = folder
+        .handles
+        .iter()
+        .filter_map(|handle| {
+            let handle = handle.clone().try_typed::<LevelAsset>().ok()?;
+            let path = asset_server.get_path(handle.id())?;
+            let file_name = path.path().file_name()?.to_string_lossy();
+            let name = file_name.trim_end_matches(LEVEL_EXTENSION);
+            Some((LevelKey::from(name), handle))
+        })
+        .collect();
+}
+
+/// Separates the sheet from the pattern name in references to a pattern of
+/// another sheet, e.g. `"overworld.pattern/cloud-single"`.
+const SHEET_SEPARATOR: char = '/';
+
+/// Sheet and name of the pattern `reference` points at, from a level or
+/// pattern of `sheet`.
+pub fn split_pattern_reference<'a>(sheet: &'a str, reference: &'a str) -> (&'a str, &'a str) {
+    reference
+        .split_once(SHEET_SEPARATOR)
+        .unwrap_or((sheet, reference))
+}
+
+/// Error returned when a pattern reference can't be resolved.
+#[derive(Debug)]
+pub enum PatternError {
+    UnknownSheet(String),
+    UnknownPattern {
+        sheet: String,
+        pattern: String,
+    },
+    /// Patterns referencing themselves through each other, as
+    /// `sheet/pattern`, the first one repeated last.
+    Cycle(Vec<String>),
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternError::UnknownSheet(sheet) => write!(f, "unknown pattern sheet {}", sheet),
+            PatternError::UnknownPattern { sheet, pattern } => {
+                write!(f, "unknown pattern {} of {}", pattern, sheet)
+            }
+            PatternError::Cycle(patterns) => {
+                write!(f, "cyclic pattern references {}", patterns.join(" -> "))
+            }
         }
     }
 }
 
-impl AssetKey for PatternKey {
-    type Asset = PatternAsset;
+impl std::error::Error for PatternError {}
+
+/// Every pattern sheet of `assets/patterns`, keyed by file name without the
+/// `.json` extension, which is the name levels give as `patternSheet`, e.g.
+/// `"overworld.pattern"`.
+#[derive(Resource, Default, Reflect)]
+#[reflect(Resource)]
+pub struct PatternSheets {
+    folder: Handle<LoadedFolder>,
+    #[reflect(ignore)]
+    sheets: HashMap<String, PatternAsset>,
 }
 
-impl FromWorld for HandleMap<PatternKey> {
+impl PatternSheets {
+    pub fn get(&self, name: &str) -> Option<&PatternAsset> {
+        self.sheets.get(name)
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, sheet: PatternAsset) {
+        self.sheets.insert(name.into(), sheet);
+    }
+
+    pub fn pattern(&self, sheet: &str, name: &str) -> Result<&PatternTilesAsset, PatternError> {
+        self.get(sheet)
+            .ok_or_else(|| PatternError::UnknownSheet(sheet.to_string()))?
+            .patterns
+            .get(name)
+            .ok_or_else(|| PatternError::UnknownPattern {
+                sheet: sheet.to_string(),
+                pattern: name.to_string(),
+            })
+    }
+
+    /// Checks that the pattern `reference` points at from `sheet`, and every
+    /// pattern it references in turn, exist and don't reference themselves.
+    pub fn check(&self, sheet: &str, reference: &str) -> Result<(), PatternError> {
+        let (sheet, name) = split_pattern_reference(sheet, reference);
+        self.check_pattern(sheet, name, &mut Vec::new(), &mut HashSet::new())
+    }
+
+    /// Checks every pattern of every sheet, see [`PatternSheets::check`].
+    pub fn validate(&self) -> Result<(), PatternError> {
+        let mut checked = HashSet::new();
+        for (sheet, patterns) in &self.sheets {
+            for name in patterns.patterns.keys() {
+                self.check_pattern(sheet, name, &mut Vec::new(), &mut checked)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// `path` holds the patterns being checked, `checked` the ones already
+    /// known to be valid.
+    fn check_pattern(
+        &self,
+        sheet: &str,
+        name: &str,
+        path: &mut Vec<String>,
+        checked: &mut HashSet<String>,
+    ) -> Result<(), PatternError> {
+        let id = format!("{}{}{}", sheet, SHEET_SEPARATOR, name);
+        if let Some(start) = path.iter().position(|other| *other == id) {
+            let mut cycle = path.split_off(start);
+            cycle.push(id);
+            return Err(PatternError::Cycle(cycle));
+        }
+        if checked.contains(&id) {
+            return Ok(());
+        }
+
+        let pattern = self.pattern(sheet, name)?;
+        path.push(id);
+        for reference in pattern
+            .tiles
+            .iter()
+            .filter_map(|tile| tile.pattern.as_ref())
+        {
+            let (sheet, name) = split_pattern_reference(sheet, reference);
+            self.check_pattern(sheet, name, path, checked)?;
+        }
+        if let Some(id) = path.pop() {
+            checked.insert(id);
+        }
+        Ok(())
+    }
+
+    pub fn all_loaded(&self, asset_server: &AssetServer) -> bool {
+        asset_server.is_loaded_with_dependencies(&self.folder)
+    }
+}
+
+impl FromWorld for PatternSheets {
     fn from_world(world: &mut World) -> Self {
         let asset_server = world.resource::<AssetServer>();
-        [
-            (
-                PatternKey::Castle,
-                asset_server.load("patterns/castle.pattern.json"),
-            ),
-            (
-                PatternKey::Overworld,
-                asset_server.load("patterns/overworld.pattern.json"),
-            ),
-            (
-                PatternKey::Underwater,
-                asset_server.load("patterns/underwater.pattern.json"),
-            ),
-            (
-                PatternKey::UnderWorld,
-                asset_server.load("patterns/underworld.pattern.json"),
-            ),
-        ]
-        .into()
+        Self {
+            folder: asset_server.load_folder("patterns"),
+            sheets: HashMap::new(),
+        }
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Reflect)]
-pub enum SpriteKey {
-    Overworld,
-    Mario,
+const PATTERN_EXTENSION: &str = ".json";
+
+/// Copies the loaded pattern sheets into [`PatternSheets`] and reports the
+/// references that can't be resolved.
+pub(crate) fn build_pattern_sheets(
+    mut pattern_sheets: ResMut<PatternSheets>,
+    asset_server: Res<AssetServer>,
+    folders: Res<Assets<LoadedFolder>>,
+    patterns: Res<Assets<PatternAsset>>,
+) {
+    let Some(folder) = folders.get(&pattern_sheets.folder) else {
+        return;
+    };
+
+    let mut sheets = HashMap::new();
+    for handle in &folder.handles {
+        let Ok(handle) = handle.clone().try_typed::<PatternAsset>() else {
+            continue;
+        };
+        let (Some(path), Some(sheet)) = (asset_server.get_path(handle.id()), patterns.get(&handle))
+        else {
+            continue;
+        };
+        let Some(file_name) = path.path().file_name() else {
+            warn!("pattern sheet {} has no file name, skipping it", path);
+            continue;
+        };
+        let name = file_name
+            .to_string_lossy()
+            .trim_end_matches(PATTERN_EXTENSION)
+            .to_string();
+        sheets.insert(name, sheet.clone());
+    }
+    pattern_sheets.sheets = sheets;
+
+    if let Err(err) = pattern_sheets.validate() {
+        error!("invalid pattern sheets: {}", err);
+    }
+}
+
+/// Frames of a named animation of a sprite sheet.
+#[derive(Debug, Clone, Default, Reflect)]
+pub struct AnimationClip {
+    /// Atlas indices of the frames.
+    pub frames: Vec<usize>,
+    pub frame_len: f32,
+}
+
+/// Atlas layout and animations built from a [`SpriteAsset`]. Every named
+/// frame is also a single frame animation of the same name.
+#[derive(Debug, Clone, Default, Reflect)]
+pub struct SpriteSheet {
+    pub texture: Handle<Image>,
+    pub layout: Handle<TextureAtlasLayout>,
+    /// Atlas index of every named frame or tile.
+    pub frames: HashMap<String, usize>,
+    pub animations: HashMap<String, AnimationClip>,
+    /// Tiles levels can place, for sheets drawn from the tileset.
+    #[reflect(ignore)]
+    pub tiles: TileSet,
 }
 
-impl AssetKey for SpriteKey {
-    type Asset = SpriteAsset;
+/// Every sprite sheet of `assets/sprites`, keyed by file name without the
+/// `.sprite.json` extension, e.g. `"koopa-green"`.
+#[derive(Resource, Default, Reflect)]
+#[reflect(Resource)]
+pub struct SpriteSheets {
+    folder: Handle<LoadedFolder>,
+    sheets: HashMap<String, SpriteSheet>,
 }
 
-impl FromWorld for HandleMap<SpriteKey> {
+impl SpriteSheets {
+    pub fn get(&self, name: &str) -> Option<&SpriteSheet> {
+        self.sheets.get(name)
+    }
+
+    pub fn all_loaded(&self, asset_server: &AssetServer) -> bool {
+        asset_server.is_loaded_with_dependencies(&self.folder)
+    }
+}
+
+impl FromWorld for SpriteSheets {
     fn from_world(world: &mut World) -> Self {
         let asset_server = world.resource::<AssetServer>();
-        [
-            (
-                SpriteKey::Overworld,
-                asset_server.load("sprites/overworld.sprite.json"),
-            ),
-            (
-                SpriteKey::Mario,
-                asset_server.load("sprites/mario.sprite.json"),
-            ),
-        ]
-        .into()
+        Self {
+            folder: asset_server.load_folder("sprites"),
+            sheets: HashMap::new(),
+        }
+    }
+}
+
+const SPRITE_EXTENSION: &str = ".sprite.json";
+
+fn build_sprite_sheets(
+    mut sprite_sheets: ResMut<SpriteSheets>,
+    asset_server: Res<AssetServer>,
+    folders: Res<Assets<LoadedFolder>>,
+    sprites: Res<Assets<SpriteAsset>>,
+    images: Res<Assets<Image>>,
+    texture_handles: Res<HandleMap<TextureKey>>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    let Some(folder) = folders.get(&sprite_sheets.folder) else {
+        return;
+    };
+
+    let mut sheets = HashMap::new();
+    for handle in &folder.handles {
+        let Ok(handle) = handle.clone().try_typed::<SpriteAsset>() else {
+            continue;
+        };
+        let (Some(path), Some(sprite)) = (asset_server.get_path(handle.id()), sprites.get(&handle))
+        else {
+            continue;
+        };
+        let file_name = path.path().file_name().unwrap().to_string_lossy();
+        let name = file_name.trim_end_matches(SPRITE_EXTENSION).to_string();
+
+        let Some(texture_key) = TextureKey::from_image_url(&sprite.image_url) else {
+            warn!(
+                "sprite sheet {} uses unknown image {}",
+                name, sprite.image_url
+            );
+            continue;
+        };
+        let texture = texture_handles[&texture_key].clone_weak();
+        let Some(image) = images.get(&texture) else {
+            warn!(
+                "sprite sheet {} uses unloaded image {}",
+                name, sprite.image_url
+            );
+            continue;
+        };
+        let size = image.size();
+
+        let mut layout = TextureAtlasLayout::new_empty(size);
+        let frames = sprite
+            .rects()
+            .into_iter()
+            .map(|(frame_name, rect)| (frame_name.to_string(), layout.add_texture(rect)))
+            .collect::<HashMap<_, _>>();
+
+        let mut animations = sprite
+            .animations
+            .iter()
+            .map(|animation| {
+                let clip = AnimationClip {
+                    frames: animation
+                        .frames
+                        .iter()
+                        .filter_map(|frame| {
+                            let index = frames.get(frame).copied();
+                            if index.is_none() {
+                                warn!(
+                                    "animation {} of {} has no frame {}",
+                                    animation.name, name, frame
+                                );
+                            }
+                            index
+                        })
+                        .collect(),
+                    frame_len: animation.frame_len,
+                };
+                (animation.name.clone(), clip)
+            })
+            .collect::<HashMap<_, _>>();
+        for (frame_name, index) in &frames {
+            animations
+                .entry(frame_name.clone())
+                .or_insert_with(|| AnimationClip {
+                    frames: vec![*index],
+                    frame_len: 0.,
+                });
+        }
+
+        let tiles = if texture_key == TextureKey::Tiles && sprite.tile_w > 0 {
+            TileSet::from_sprite(&name, sprite, size.x / sprite.tile_w)
+        } else {
+            TileSet::default()
+        };
+
+        sheets.insert(
+            name,
+            SpriteSheet {
+                texture,
+                layout: layouts.add(layout),
+                frames,
+                animations,
+                tiles,
+            },
+        );
     }
+    sprite_sheets.sheets = sheets;
 }
 
 pub trait AssetKey: Sized {
@@ -261,3 +683,122 @@ impl<K: AssetKey> HandleMap<K> {
             .all(|x| asset_server.is_loaded_with_dependencies(x))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn levels(names: &[&str]) -> Levels {
+        Levels {
+            folder: Handle::default(),
+            levels: names
+                .iter()
+                .map(|&name| (LevelKey::from(name), Handle::default()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn levels_keys_list_stages_in_order_first() {
+        let levels = levels(&["debug-flag", "10-1", "1-2", "uw-exit", "2-1", "1-1"]);
+
+        assert_eq!(
+            levels.keys(),
+            ["1-1", "1-2", "2-1", "10-1", "debug-flag", "uw-exit"].map(LevelKey::from)
+        );
+    }
+
+    #[test]
+    fn levels_next_is_the_following_stage() {
+        let levels = levels(&["1-1", "1-2", "1-4", "2-1", "coin-room-1"]);
+
+        assert_eq!(levels.next(&"1-1".into()), Some("1-2".into()));
+        assert_eq!(levels.next(&"1-2".into()), Some("1-4".into()));
+        assert_eq!(levels.next(&"1-4".into()), Some("2-1".into()));
+        assert_eq!(levels.next(&"2-1".into()), None);
+        assert_eq!(levels.next(&"coin-room-1".into()), None);
+    }
+
+    /// A pattern made of a single tile of each of `references`.
+    fn pattern(references: &[&str]) -> PatternTilesAsset {
+        PatternTilesAsset {
+            tiles: references
+                .iter()
+                .map(|reference| LevelTileAsset {
+                    pattern: Some(reference.to_string()),
+                    ranges: vec![vec![0, 0]],
+                    ..Default::default()
+                })
+                .collect(),
+        }
+    }
+
+    fn pattern_sheets(sheets: &[(&str, &[(&str, &[&str])])]) -> PatternSheets {
+        let mut pattern_sheets = PatternSheets::default();
+        for (name, patterns) in sheets {
+            let patterns = patterns
+                .iter()
+                .map(|(name, references)| (name.to_string(), pattern(references)))
+                .collect();
+            pattern_sheets.insert(*name, PatternAsset { patterns });
+        }
+        pattern_sheets
+    }
+
+    #[test]
+    fn pattern_sheets_check_resolves_references_across_sheets() {
+        let sheets = pattern_sheets(&[
+            ("a.pattern", &[("pipe", &["b.pattern/cap"])]),
+            ("b.pattern", &[("cap", &[])]),
+        ]);
+
+        assert!(sheets.check("a.pattern", "pipe").is_ok());
+        assert!(sheets.check("b.pattern", "a.pattern/pipe").is_ok());
+        assert!(sheets.validate().is_ok());
+    }
+
+    #[test]
+    fn pattern_sheets_check_reports_unknown_patterns() {
+        let sheets = pattern_sheets(&[("a.pattern", &[("pipe", &["cap"])])]);
+
+        assert!(matches!(
+            sheets.check("a.pattern", "pipe"),
+            Err(PatternError::UnknownPattern { sheet, pattern })
+                if sheet == "a.pattern" && pattern == "cap"
+        ));
+        assert!(matches!(
+            sheets.check("a.pattern", "c.pattern/pipe"),
+            Err(PatternError::UnknownSheet(sheet)) if sheet == "c.pattern"
+        ));
+    }
+
+    #[test]
+    fn pattern_sheets_check_reports_cycles() {
+        let sheets = pattern_sheets(&[
+            (
+                "a.pattern",
+                &[("loop", &["b.pattern/back"]), ("entry", &["loop"])],
+            ),
+            ("b.pattern", &[("back", &["a.pattern/loop"])]),
+        ]);
+
+        let Err(PatternError::Cycle(cycle)) = sheets.check("a.pattern", "entry") else {
+            panic!("cycle not detected");
+        };
+        assert_eq!(
+            cycle,
+            ["a.pattern/loop", "b.pattern/back", "a.pattern/loop"]
+        );
+        assert!(matches!(sheets.validate(), Err(PatternError::Cycle(_))));
+    }
+
+    #[test]
+    fn pattern_sheets_check_reports_self_references() {
+        let sheets = pattern_sheets(&[("a.pattern", &[("loop", &["loop"])])]);
+
+        let Err(PatternError::Cycle(cycle)) = sheets.check("a.pattern", "loop") else {
+            panic!("cycle not detected");
+        };
+        assert_eq!(cycle, ["a.pattern/loop", "a.pattern/loop"]);
+    }
+}
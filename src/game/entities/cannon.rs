@@ -0,0 +1,39 @@
+use bevy::prelude::*;
+
+use crate::{
+    game::{
+        assets::LevelEntityAsset,
+        spawn::entities::{EntityKind, EntitySprite, RegisterEntity},
+    },
+    screen::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Cannon>();
+    app.register_entity(EntityKind {
+        name: "cannon",
+        spawn: spawn_cannon,
+    });
+}
+
+/// Where a cannon stands. Its barrel is drawn by the tilemap.
+#[derive(Component, Debug, Clone, PartialEq, Eq, Default, Reflect)]
+#[reflect(Component)]
+pub struct Cannon;
+
+pub fn spawn_cannon(
+    commands: &mut Commands,
+    _sprite: EntitySprite,
+    entity_asset: &LevelEntityAsset,
+    pos_x: u32,
+    pos_y: u32,
+) -> Entity {
+    commands
+        .spawn((
+            Name::new(entity_asset.name.clone()),
+            Cannon,
+            SpatialBundle::from_transform(Transform::from_xyz(pos_x as f32, pos_y as f32, 1.)),
+            StateScoped(Screen::Playing),
+        ))
+        .id()
+}
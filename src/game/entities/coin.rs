@@ -0,0 +1,206 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_ecs_tilemap::{
+    map::{TilemapGridSize, TilemapId, TilemapSize},
+    tiles::{TilePos, TileStorage},
+};
+
+use crate::{
+    config::TILE_SIZE,
+    game::{
+        animations::animator::Animator,
+        assets::{LevelAsset, Levels, SpriteSheets},
+        entities::{Dying, Lives, Player},
+        physics::{BoxCollider, Drag, Forces, Mass, PhysicsStep, Pos, PreSolveVel, PrevPos, Vel},
+        spawn::{entities::EntitySprite, level::LevelEntity},
+        tiles::{
+            components::{Behaviour, BlockContents, BlockOpened, CollisionLayer},
+            utils::covered_cells,
+        },
+        GameState,
+    },
+    screen::Screen,
+};
+
+const COINS_PER_LIFE: u32 = 100;
+const POP_UP_SPEED: f32 = 400.;
+const POP_UP_DURATION: Duration = Duration::from_millis(400);
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Coins>();
+    app.add_event::<CoinCollected>();
+    app.add_systems(
+        FixedUpdate,
+        (collect_coin_tiles, release_coins, remove_pop_up_coins)
+            .chain()
+            .after(PhysicsStep::SolveVelocities)
+            .run_if(in_state(Screen::Playing)),
+    );
+}
+
+/// Coins collected towards the next life.
+#[derive(Resource, Debug, Default)]
+pub struct Coins(pub u32);
+
+/// Sent for every coin collected, from a coin tile or a coin block.
+#[derive(Event, Debug, Clone)]
+pub struct CoinCollected {
+    pub by: Entity,
+    /// Where the coin was, in world space.
+    pub pos: Vec2,
+}
+
+/// A coin jumping out of a coin block. It is already collected.
+#[derive(Component, Debug)]
+pub struct PopUpCoin(Timer);
+
+/// Counts a coin, trading every hundred for a life.
+fn add_coin(coins: &mut Coins, lives: &mut Lives) {
+    coins.0 += 1;
+    if coins.0 >= COINS_PER_LIFE {
+        coins.0 -= COINS_PER_LIFE;
+        lives.0 += 1;
+    }
+}
+
+/// Living players collect the coin tiles they overlap. Collected tiles are
+/// removed from their layer and from the collision layer.
+fn collect_coin_tiles(
+    mut commands: Commands,
+    mut coins: ResMut<Coins>,
+    mut lives: ResMut<Lives>,
+    mut coin_collected: EventWriter<CoinCollected>,
+    player_q: Query<(Entity, &Pos, &BoxCollider), (With<Player>, Without<Dying>)>,
+    mut collision_q: Query<
+        (&TilemapSize, &TilemapGridSize, &mut TileStorage),
+        With<CollisionLayer>,
+    >,
+    mut layer_q: Query<&mut TileStorage, Without<CollisionLayer>>,
+    tile_q: Query<(&Behaviour, &TilemapId)>,
+) {
+    let Ok((map_size, grid_size, mut collision_storage)) = collision_q.get_single_mut() else {
+        return;
+    };
+    for (player, pos, box_) in &player_q {
+        let max = pos.0 + box_.size;
+        for x in covered_cells(pos.0.x, max.x, grid_size.x) {
+            for y in covered_cells(pos.0.y, max.y, grid_size.y) {
+                let Some(tile_pos) = TilePos::from_i32_pair(x, y, map_size) else {
+                    continue;
+                };
+                let Some(tile) = collision_storage.get(&tile_pos) else {
+                    continue;
+                };
+                let Ok((Behaviour::Coin, tilemap_id)) = tile_q.get(tile) else {
+                    continue;
+                };
+                collision_storage.remove(&tile_pos);
+                if let Ok(mut tile_storage) = layer_q.get_mut(tilemap_id.0) {
+                    tile_storage.remove(&tile_pos);
+                }
+                commands.entity(tile).despawn_recursive();
+
+                add_coin(&mut coins, &mut lives);
+                coin_collected.send(CoinCollected {
+                    by: player,
+                    pos: Vec2::new(x as f32, y as f32) * TILE_SIZE as f32,
+                });
+            }
+        }
+    }
+}
+
+/// Coin blocks send a coin jumping out of them, counted straight away. The
+/// coin is drawn from the level's sprite sheet, like its tiles.
+fn release_coins(
+    mut commands: Commands,
+    mut coins: ResMut<Coins>,
+    mut lives: ResMut<Lives>,
+    mut block_opened: EventReader<BlockOpened>,
+    mut coin_collected: EventWriter<CoinCollected>,
+    sprite_sheets: Res<SpriteSheets>,
+    level_handles: Res<Levels>,
+    levels: Res<Assets<LevelAsset>>,
+    gs: Res<GameState>,
+) {
+    let sheet = level_handles
+        .get(&gs.current_level)
+        .and_then(|handle| levels.get(handle))
+        .map_or("", |level| level.sprite_sheet.as_str());
+    for BlockOpened {
+        tile_pos,
+        by,
+        contents,
+        ..
+    } in block_opened.read()
+    {
+        if *contents != BlockContents::Coin {
+            continue;
+        }
+        let pos = Vec2::new(tile_pos.x as f32, (tile_pos.y + 1) as f32) * TILE_SIZE as f32;
+        let sprite = EntitySprite::new(&sprite_sheets, sheet);
+        commands.spawn((
+            Name::new("Pop-up Coin"),
+            PopUpCoin(Timer::new(POP_UP_DURATION, TimerMode::Once)),
+            Animator::new(sprite.animations, "coin"),
+            SpriteBundle {
+                texture: sprite.texture,
+                transform: Transform::from_translation(pos.extend(1.)),
+                ..Default::default()
+            },
+            TextureAtlas {
+                layout: sprite.layout,
+                index: 0,
+            },
+            // A body without a collider, so it only rises and falls.
+            (
+                Pos(pos),
+                PrevPos(pos),
+                Vel(Vec2::new(0., POP_UP_SPEED)),
+                PreSolveVel::default(),
+                Mass::default(),
+                Forces::default(),
+                Drag::default(),
+            ),
+            StateScoped(Screen::Playing),
+            LevelEntity,
+        ));
+
+        add_coin(&mut coins, &mut lives);
+        coin_collected.send(CoinCollected { by: *by, pos });
+    }
+}
+
+fn remove_pop_up_coins(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut coin_q: Query<(Entity, &mut PopUpCoin)>,
+) {
+    for (entity, mut coin) in &mut coin_q {
+        if coin.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_coin_counts_coins() {
+        let mut coins = Coins(10);
+        let mut lives = Lives(3);
+        add_coin(&mut coins, &mut lives);
+        assert_eq!((coins.0, lives.0), (11, 3));
+    }
+
+    #[test]
+    fn add_coin_trades_a_hundred_coins_for_a_life() {
+        let mut coins = Coins(COINS_PER_LIFE - 1);
+        let mut lives = Lives(3);
+        add_coin(&mut coins, &mut lives);
+        assert_eq!((coins.0, lives.0), (0, 4));
+    }
+}
@@ -0,0 +1,39 @@
+use bevy::prelude::*;
+
+use crate::{
+    game::{
+        assets::LevelEntityAsset,
+        spawn::entities::{EntityKind, EntitySprite, RegisterEntity},
+    },
+    screen::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Flag>();
+    app.register_entity(EntityKind {
+        name: "flag-pole",
+        spawn: spawn_flag,
+    });
+}
+
+/// The flag at the top of a flag pole. The pole itself is made of tiles.
+#[derive(Component, Debug, Clone, PartialEq, Eq, Default, Reflect)]
+#[reflect(Component)]
+pub struct Flag;
+
+pub fn spawn_flag(
+    commands: &mut Commands,
+    _sprite: EntitySprite,
+    entity_asset: &LevelEntityAsset,
+    pos_x: u32,
+    pos_y: u32,
+) -> Entity {
+    commands
+        .spawn((
+            Name::new(entity_asset.name.clone()),
+            Flag,
+            SpatialBundle::from_transform(Transform::from_xyz(pos_x as f32, pos_y as f32, 1.)),
+            StateScoped(Screen::Playing),
+        ))
+        .id()
+}
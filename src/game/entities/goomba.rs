@@ -1,35 +1,68 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 
 use crate::{
     game::{
-        animations::goomba_animation::GoombaAnimation,
-        assets::{HandleMap, TextureKey},
-        physics::{BoxCollider, DynamicBoxBundle, Pos},
-        spawn::entities::{EntityKey, Mob, TextureAtlasLayoutEntities},
-        traits::solid::Obstruct,
+        animations::animator::Animator,
+        assets::LevelEntityAsset,
+        movement::MovementController,
+        physics::{Aabb, BoxCollider, DynamicBoxBundle, Kinematic, PhysicsStep, Pos, Vel},
+        spawn::entities::{EntityKind, EntitySprite, Mob, RegisterEntity},
+        traits::{go::Go, patrol::Patrol, solid::Obstruct, stomp::Stomped},
     },
     screen::Screen,
 };
 
+/// How long a flattened goomba stays on screen.
+const FLAT_DURATION: Duration = Duration::from_millis(500);
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Goomba>();
+    app.register_entity(EntityKind {
+        name: "goomba-brown",
+        spawn: spawn_goomba,
+    })
+    .register_entity(EntityKind {
+        name: "goomba-blue",
+        spawn: spawn_goomba,
+    });
+
+    app.add_systems(
+        FixedUpdate,
+        (flatten_goombas, remove_flat_goombas)
+            .chain()
+            .after(PhysicsStep::SolveVelocities),
+    );
+}
+
+#[derive(Component, Debug, Clone, PartialEq, Eq, Default, Reflect)]
+#[reflect(Component)]
+pub struct Goomba;
+
+/// A stomped goomba, despawned when the timer finishes.
+#[derive(Component, Debug)]
+pub struct Flat(Timer);
+
 pub fn spawn_goomba(
     commands: &mut Commands,
-    image_handles: &Res<HandleMap<TextureKey>>,
-    atlas_layout_handles: &ResMut<TextureAtlasLayoutEntities>,
-    key: EntityKey,
+    sprite: EntitySprite,
+    entity_asset: &LevelEntityAsset,
     pos_x: u32,
     pos_y: u32,
 ) -> Entity {
     commands
         .spawn((
-            Name::new(key.to_string().to_string()),
+            Name::new(entity_asset.name.clone()),
+            Goomba,
             Mob {},
-            GoombaAnimation::walking(),
+            Animator::new(sprite.animations, "walk"),
             SpriteBundle {
-                texture: image_handles[&TextureKey::Entities].clone_weak(),
+                texture: sprite.texture,
                 ..Default::default()
             },
             TextureAtlas {
-                layout: atlas_layout_handles.0[&key].clone(),
+                layout: sprite.layout,
                 index: 0,
             },
             DynamicBoxBundle {
@@ -39,8 +72,47 @@ pub fn spawn_goomba(
                 },
                 ..Default::default()
             },
-            Obstruct(false),
+            Obstruct::default(),
+            MovementController::default(),
+            Go {
+                acceleration: 200.,
+                deceleration: 200.,
+                ..Default::default()
+            },
+            Patrol::default(),
             StateScoped(Screen::Playing),
         ))
         .id()
 }
+
+/// Stomped goombas stop, lose their collider so nothing bumps into them any
+/// more, and show the flat frame for a moment.
+fn flatten_goombas(
+    mut commands: Commands,
+    mut stomped_events: EventReader<Stomped>,
+    mut goomba_q: Query<(&mut Animator, &mut Vel), (With<Goomba>, With<Mob>)>,
+) {
+    for Stomped { mob, .. } in stomped_events.read() {
+        let Ok((mut animator, mut vel)) = goomba_q.get_mut(*mob) else {
+            continue;
+        };
+        animator.play("flat");
+        vel.0 = Vec2::ZERO;
+        commands
+            .entity(*mob)
+            .remove::<(Mob, Patrol, Aabb, BoxCollider)>()
+            .insert((Kinematic, Flat(Timer::new(FLAT_DURATION, TimerMode::Once))));
+    }
+}
+
+fn remove_flat_goombas(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut flat_q: Query<(Entity, &mut Flat)>,
+) {
+    for (entity, mut flat) in &mut flat_q {
+        if flat.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, utils::HashSet};
+
+use crate::{
+    game::{
+        animations::animator::Animator,
+        assets::LevelEntityAsset,
+        movement::MovementController,
+        physics::{BoxCollider, Contacts, DynamicBoxBundle, PhysicsStep, Pos, Vel},
+        spawn::entities::{EntityKind, EntitySprite, Mob, RegisterEntity},
+        tiles::components::XSide,
+        traits::{
+            go::Go,
+            killable::{Dead, KnockedOut},
+            patrol::Patrol,
+            solid::Obstruct,
+            stomp::{Stomped, Stomper},
+        },
+    },
+    screen::Screen,
+    AppSet,
+};
+
+/// Kinds that keep to their platform. Blue koopas stand in for the red ones
+/// of the original game, which there is no sprite sheet for.
+const LEDGE_TURNING_KINDS: &[&str] = &["koopa-blue"];
+
+const KOOPA_SIZE: Vec2 = Vec2::new(16., 24.);
+const SHELL_SIZE: Vec2 = Vec2::new(16., 16.);
+const SHELL_SPEED: f32 = 200.;
+/// How long a koopa stays in its shell before walking out again.
+const HIDE_DURATION: Duration = Duration::from_secs(5);
+/// How long before walking out the legs start showing.
+const WAKE_WARNING: Duration = Duration::from_millis(1500);
+/// How long a kicked shell leaves the kicker alone.
+const KICK_GRACE: Duration = Duration::from_millis(200);
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Koopa>();
+    app.register_entity(EntityKind {
+        name: "koopa-green",
+        spawn: spawn_koopa,
+    })
+    .register_entity(EntityKind {
+        name: "koopa-blue",
+        spawn: spawn_koopa,
+    });
+
+    app.add_systems(
+        FixedUpdate,
+        (
+            update_shells.in_set(PhysicsStep::PreIntegrate),
+            (hide_in_shells, kick_shells, knock_out_with_shells)
+                .chain()
+                .after(PhysicsStep::SolveVelocities),
+        ),
+    );
+    app.add_systems(Update, flip_koopas.in_set(AppSet::Update));
+}
+
+#[derive(Component, Debug, Clone, PartialEq, Eq, Default, Reflect)]
+#[reflect(Component)]
+pub struct Koopa;
+
+/// A koopa pulled into its shell by a stomp.
+#[derive(Component, Debug)]
+pub struct Shell {
+    pub state: ShellState,
+    /// Restored on the patrol once the koopa walks out.
+    turn_at_ledges: bool,
+}
+
+#[derive(Debug)]
+pub enum ShellState {
+    /// Resting, kicked by the first stomper to touch it.
+    Hiding(Timer),
+    /// Kicked, knocking out the mobs in its way. Only becomes a [`Mob`] that
+    /// hurts stompers once the grace period is over.
+    Sliding {
+        direction: f32,
+        grace: Timer,
+        kills: u32,
+    },
+}
+
+pub fn spawn_koopa(
+    commands: &mut Commands,
+    sprite: EntitySprite,
+    entity_asset: &LevelEntityAsset,
+    pos_x: u32,
+    pos_y: u32,
+) -> Entity {
+    commands
+        .spawn((
+            Name::new(entity_asset.name.clone()),
+            Mob {},
+            Koopa,
+            Animator::new(sprite.animations, "walk"),
+            SpriteBundle {
+                texture: sprite.texture,
+                ..Default::default()
+            },
+            TextureAtlas {
+                layout: sprite.layout,
+                index: 0,
+            },
+            DynamicBoxBundle {
+                pos: Pos(Vec2::new(pos_x as f32, pos_y as f32)),
+                collider: BoxCollider { size: KOOPA_SIZE },
+                ..Default::default()
+            },
+            Obstruct::default(),
+            walker(LEDGE_TURNING_KINDS.contains(&entity_asset.name.as_str())),
+            StateScoped(Screen::Playing),
+        ))
+        .id()
+}
+
+fn walker(turn_at_ledges: bool) -> impl Bundle {
+    (
+        MovementController::default(),
+        Go {
+            acceleration: 200.,
+            deceleration: 200.,
+            ..Default::default()
+        },
+        Patrol {
+            turn_at_ledges,
+            ..Default::default()
+        },
+    )
+}
+
+/// Stomping a walking koopa or a sliding shell leaves a resting shell.
+fn hide_in_shells(
+    mut commands: Commands,
+    mut stomped_events: EventReader<Stomped>,
+    mut koopa_q: Query<
+        (
+            &mut Animator,
+            &mut Vel,
+            &mut BoxCollider,
+            Option<&Patrol>,
+            Option<&Shell>,
+        ),
+        (With<Koopa>, Without<Dead>),
+    >,
+) {
+    for Stomped { mob, .. } in stomped_events.read() {
+        let Ok((mut animator, mut vel, mut collider, patrol, shell)) = koopa_q.get_mut(*mob) else {
+            continue;
+        };
+        let turn_at_ledges = patrol
+            .map(|patrol| patrol.turn_at_ledges)
+            .or(shell.map(|shell| shell.turn_at_ledges))
+            .unwrap_or_default();
+        animator.play("hiding");
+        vel.0.x = 0.;
+        collider.size = SHELL_SIZE;
+        commands
+            .entity(*mob)
+            .remove::<(Mob, MovementController, Go, Patrol)>()
+            .insert(Shell {
+                state: ShellState::Hiding(Timer::new(HIDE_DURATION, TimerMode::Once)),
+                turn_at_ledges,
+            });
+    }
+}
+
+/// Touching a resting shell sends it sliding away from the stomper. The
+/// stomp that just made a shell rest doesn't kick it, the stomper's contact
+/// is still there this step.
+fn kick_shells(
+    contacts: Res<Contacts>,
+    mut stomped_events: EventReader<Stomped>,
+    stomper_q: Query<&Pos, With<Stomper>>,
+    mut shell_q: Query<(&mut Shell, &Pos), Without<Dead>>,
+) {
+    let stomped = stomped_events
+        .read()
+        .map(|stomped| (stomped.stomper, stomped.mob))
+        .collect::<HashSet<_>>();
+    for &(entity_a, entity_b, _) in &contacts.0 {
+        for (stomper, shell) in [(entity_a, entity_b), (entity_b, entity_a)] {
+            if stomped.contains(&(stomper, shell)) {
+                continue;
+            }
+            let (Ok(stomper_pos), Ok((mut shell, shell_pos))) =
+                (stomper_q.get(stomper), shell_q.get_mut(shell))
+            else {
+                continue;
+            };
+            if !matches!(shell.state, ShellState::Hiding(_)) {
+                continue;
+            }
+            let direction = (shell_pos.0.x - stomper_pos.0.x).signum();
+            shell.state = ShellState::Sliding {
+                direction: if direction == 0. { 1. } else { direction },
+                grace: Timer::new(KICK_GRACE, TimerMode::Once),
+                kills: 0,
+            };
+        }
+    }
+}
+
+/// Sliding shells knock out every mob and shell they run into, counting the
+/// kills so each one is worth more.
+fn knock_out_with_shells(
+    contacts: Res<Contacts>,
+    mut shell_q: Query<&mut Shell, Without<Dead>>,
+    target_q: Query<&Pos, (Or<(With<Mob>, With<Shell>)>, Without<Dead>)>,
+    mut knocked_out_events: EventWriter<KnockedOut>,
+) {
+    let mut knocked_out = HashSet::new();
+    for &(entity_a, entity_b, _) in &contacts.0 {
+        for (shell, target) in [(entity_a, entity_b), (entity_b, entity_a)] {
+            let (Ok(mut shell_state), Ok(target_pos)) =
+                (shell_q.get_mut(shell), target_q.get(target))
+            else {
+                continue;
+            };
+            let ShellState::Sliding { kills, .. } = &mut shell_state.state else {
+                continue;
+            };
+            if !knocked_out.insert(target) {
+                continue;
+            }
+            knocked_out_events.send(KnockedOut {
+                mob: target,
+                by: shell,
+                combo: *kills,
+                pos: target_pos.0,
+            });
+            *kills += 1;
+        }
+    }
+}
+
+/// Slides kicked shells at a constant speed, bouncing them off walls, and
+/// lets resting koopas walk out once their time is up.
+fn update_shells(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut shell_q: Query<
+        (
+            Entity,
+            &mut Shell,
+            &mut Animator,
+            &mut Vel,
+            &mut BoxCollider,
+            &Obstruct,
+        ),
+        Without<Dead>,
+    >,
+) {
+    for (entity, mut shell, mut animator, mut vel, mut collider, obstruct) in &mut shell_q {
+        let turn_at_ledges = shell.turn_at_ledges;
+        match &mut shell.state {
+            ShellState::Hiding(timer) => {
+                timer.tick(time.delta());
+                if timer.finished() {
+                    animator.play("walk");
+                    collider.size = KOOPA_SIZE;
+                    commands
+                        .entity(entity)
+                        .remove::<Shell>()
+                        .insert((Mob {}, walker(turn_at_ledges)));
+                } else if timer.remaining() <= WAKE_WARNING {
+                    animator.play("wake");
+                }
+            }
+            ShellState::Sliding {
+                direction, grace, ..
+            } => {
+                match obstruct.x_side {
+                    Some(XSide::Left) => *direction = -1.,
+                    Some(XSide::Right) => *direction = 1.,
+                    None => {}
+                }
+                vel.0.x = *direction * SHELL_SPEED;
+                if grace.tick(time.delta()).just_finished() {
+                    commands.entity(entity).insert(Mob {});
+                }
+            }
+        }
+    }
+}
+
+/// Koopa frames face left.
+fn flip_koopas(mut koopa_q: Query<(&mut Sprite, &Go), (With<Koopa>, Changed<Go>)>) {
+    for (mut sprite, go) in &mut koopa_q {
+        sprite.flip_x = go.heading > 0;
+    }
+}
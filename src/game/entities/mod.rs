@@ -1,4 +1,30 @@
+pub mod cannon;
+pub mod coin;
+pub mod flag;
 pub mod goomba;
+pub mod koopa;
+pub mod pipe_portal;
+pub mod piranha_plant;
 pub mod player;
+pub mod power_up;
 
-pub use self::{goomba::*, player::*};
+use bevy::prelude::*;
+
+pub use self::{
+    cannon::*, coin::*, flag::*, goomba::*, koopa::*, pipe_portal::*, piranha_plant::*, player::*,
+    power_up::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_plugins((
+        cannon::plugin,
+        coin::plugin,
+        flag::plugin,
+        goomba::plugin,
+        koopa::plugin,
+        pipe_portal::plugin,
+        piranha_plant::plugin,
+        player::plugin,
+        power_up::plugin,
+    ));
+}
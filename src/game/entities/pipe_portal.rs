@@ -0,0 +1,256 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    game::{
+        assets::{LevelEntityAsset, LevelKey},
+        entities::Player,
+        movement::{ControllerDirection, MovementController},
+        physics::{Aabb, Forces, Kinematic, PhysicsStep, Pos, PrevPos, Vel},
+        spawn::{
+            entities::{EntityKind, EntitySprite, RegisterEntity},
+            level::ChangeLevel,
+        },
+        GameState,
+    },
+    screen::Screen,
+};
+
+const PORTAL_SIZE: Vec2 = Vec2::new(24., 30.);
+const TRAVEL_DISTANCE: f32 = 32.;
+const TRAVEL_DURATION: Duration = Duration::from_millis(1000);
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<PipePortal>();
+    app.init_resource::<PortalReturns>();
+    app.init_resource::<PendingOutlet>();
+    app.register_entity(EntityKind {
+        name: "pipe-portal",
+        spawn: spawn_pipe_portal,
+    });
+    app.add_systems(
+        FixedUpdate,
+        (enter_pipe_portal, place_at_outlet, travel_pipe)
+            .chain()
+            .after(PhysicsStep::SolveVelocities)
+            .run_if(in_state(Screen::Playing)),
+    );
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum PipeDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl PipeDirection {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "UP" => Some(Self::Up),
+            "DOWN" => Some(Self::Down),
+            "LEFT" => Some(Self::Left),
+            "RIGHT" => Some(Self::Right),
+            _ => None,
+        }
+    }
+
+    fn to_vec(self) -> Vec2 {
+        match self {
+            Self::Up => Vec2::Y,
+            Self::Down => Vec2::NEG_Y,
+            Self::Left => Vec2::NEG_X,
+            Self::Right => Vec2::X,
+        }
+    }
+
+    /// Whether the player is pushing into a pipe facing this direction.
+    fn is_pressed(self, controller: &MovementController) -> bool {
+        match self {
+            Self::Up => false,
+            Self::Down => controller.down,
+            Self::Left => controller.moving == ControllerDirection::Left,
+            Self::Right => controller.moving == ControllerDirection::Right,
+        }
+    }
+}
+
+/// A pipe the player can travel through. Portals without `goes_to` lead
+/// back to where the player came from, portals with an `id` are outlets
+/// the player can come out of.
+#[derive(Component, Debug, Reflect)]
+#[reflect(Component)]
+pub struct PipePortal {
+    pub id: Option<String>,
+    pub dir: PipeDirection,
+    pub goes_to: Option<LevelKey>,
+    pub back_to: Option<String>,
+    pub area: Aabb,
+}
+
+/// Levels and outlets to return to, most recent last.
+#[derive(Resource, Debug, Default)]
+pub struct PortalReturns(pub Vec<(LevelKey, Option<String>)>);
+
+/// Outlet of the next level the player comes out of once it is spawned.
+#[derive(Resource, Debug, Default)]
+struct PendingOutlet(Option<String>);
+
+#[derive(Debug)]
+struct PortalDestination {
+    level: LevelKey,
+    outlet: Option<String>,
+}
+
+/// Slides the player through a pipe, then loads the destination if any.
+#[derive(Component, Debug)]
+pub struct PipeTravel {
+    dir: PipeDirection,
+    timer: Timer,
+    destination: Option<PortalDestination>,
+}
+
+impl PipeTravel {
+    fn new(dir: PipeDirection, destination: Option<PortalDestination>) -> Self {
+        Self {
+            dir,
+            timer: Timer::new(TRAVEL_DURATION, TimerMode::Once),
+            destination,
+        }
+    }
+}
+
+pub fn spawn_pipe_portal(
+    commands: &mut Commands,
+    _sprite: EntitySprite,
+    entity_asset: &LevelEntityAsset,
+    pos_x: u32,
+    pos_y: u32,
+) -> Entity {
+    let props = &entity_asset.props;
+    let dir = props.dir.as_deref().and_then(PipeDirection::from_name);
+    let dir = dir.unwrap_or_else(|| {
+        warn!("pipe portal without a valid dir: {:?}", props.dir);
+        PipeDirection::Down
+    });
+    let goes_to = props
+        .goes_to
+        .as_ref()
+        .map(|goes_to| LevelKey::from(goes_to.name.as_str()));
+
+    commands
+        .spawn((
+            Name::new("PipePortal"),
+            PipePortal {
+                id: entity_asset.id.clone(),
+                dir,
+                goes_to,
+                back_to: props.back_to.clone(),
+                area: Aabb::from_vec_size(Vec2::new(pos_x as f32, pos_y as f32), PORTAL_SIZE),
+            },
+        ))
+        .id()
+}
+
+fn enter_pipe_portal(
+    mut commands: Commands,
+    gs: Res<GameState>,
+    mut returns: ResMut<PortalReturns>,
+    portal_q: Query<&PipePortal>,
+    mut player_q: Query<
+        (Entity, &Aabb, &MovementController, &mut Vel),
+        (With<Player>, Without<PipeTravel>),
+    >,
+) {
+    for (entity, aabb, controller, mut vel) in &mut player_q {
+        let Some(portal) = portal_q
+            .iter()
+            .find(|portal| portal.area.intersects(aabb) && portal.dir.is_pressed(controller))
+        else {
+            continue;
+        };
+
+        let destination = match &portal.goes_to {
+            Some(level) => {
+                returns
+                    .0
+                    .push((gs.current_level.clone(), portal.back_to.clone()));
+                PortalDestination {
+                    level: level.clone(),
+                    outlet: None,
+                }
+            }
+            None => match returns.0.pop() {
+                Some((level, outlet)) => PortalDestination { level, outlet },
+                None => continue,
+            },
+        };
+
+        vel.0 = Vec2::ZERO;
+        commands
+            .entity(entity)
+            .insert((Kinematic, PipeTravel::new(portal.dir, Some(destination))));
+    }
+}
+
+fn place_at_outlet(
+    mut commands: Commands,
+    mut pending: ResMut<PendingOutlet>,
+    portal_q: Query<&PipePortal>,
+    mut player_q: Query<(Entity, &mut Pos, &mut PrevPos), With<Player>>,
+) {
+    let Some(id) = pending.0.take() else {
+        return;
+    };
+    let Some(portal) = portal_q
+        .iter()
+        .find(|portal| portal.id.as_ref() == Some(&id))
+    else {
+        warn!("no pipe portal outlet with id {}", id);
+        return;
+    };
+
+    for (entity, mut pos, mut prev_pos) in &mut player_q {
+        // Start inside the pipe and come out the way the outlet faces.
+        let start = portal.area.min - portal.dir.to_vec() * TRAVEL_DISTANCE;
+        pos.0 = start;
+        prev_pos.0 = start;
+        commands
+            .entity(entity)
+            .insert((Kinematic, PipeTravel::new(portal.dir, None)));
+    }
+}
+
+fn travel_pipe(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pending: ResMut<PendingOutlet>,
+    mut player_q: Query<(
+        Entity,
+        &mut PipeTravel,
+        &mut Pos,
+        &mut PrevPos,
+        &mut Vel,
+        &mut Forces,
+    )>,
+) {
+    let speed = TRAVEL_DISTANCE / TRAVEL_DURATION.as_secs_f32();
+    for (entity, mut travel, mut pos, mut prev_pos, mut vel, mut forces) in &mut player_q {
+        travel.timer.tick(time.delta());
+        prev_pos.0 = pos.0;
+        pos.0 += travel.dir.to_vec() * speed * time.delta_seconds();
+        vel.0 = Vec2::ZERO;
+        // Nothing integrates the forces pushed while travelling.
+        forces.0.clear();
+
+        if travel.timer.finished() {
+            commands.entity(entity).remove::<(Kinematic, PipeTravel)>();
+            if let Some(destination) = travel.destination.take() {
+                pending.0 = destination.outlet;
+                commands.trigger(ChangeLevel(destination.level));
+            }
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use bevy::prelude::*;
+
+use crate::{
+    game::{
+        animations::animator::Animator,
+        assets::LevelEntityAsset,
+        spawn::entities::{EntityKind, EntitySprite, RegisterEntity},
+    },
+    screen::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<PiranhaPlant>();
+    app.register_entity(EntityKind {
+        name: "piranha-plant",
+        spawn: spawn_piranha_plant,
+    });
+}
+
+/// A plant sitting in a pipe. It doesn't move, so it has no physics body.
+#[derive(Component, Debug, Clone, PartialEq, Eq, Default, Reflect)]
+#[reflect(Component)]
+pub struct PiranhaPlant;
+
+pub fn spawn_piranha_plant(
+    commands: &mut Commands,
+    sprite: EntitySprite,
+    entity_asset: &LevelEntityAsset,
+    pos_x: u32,
+    pos_y: u32,
+) -> Entity {
+    commands
+        .spawn((
+            Name::new(entity_asset.name.clone()),
+            PiranhaPlant,
+            Animator::new(sprite.animations, "chew"),
+            SpriteBundle {
+                texture: sprite.texture,
+                transform: Transform::from_xyz(pos_x as f32, pos_y as f32, 1.),
+                ..Default::default()
+            },
+            TextureAtlas {
+                layout: sprite.layout,
+                index: 0,
+            },
+            StateScoped(Screen::Playing),
+        ))
+        .id()
+}
@@ -1,24 +1,98 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use crate::{
-    config::{FAST_DRAG, SLOW_DRAG},
+    config::{FAST_DRAG, SLOW_DRAG, TILE_SIZE},
     game::{
-        animations::player_animation::PlayerAnimation,
-        assets::{HandleMap, TextureKey},
+        animations::animator::Animator,
+        assets::LevelEntityAsset,
+        entities::{Invincible, Killed, PowerState, PowerTransition},
         movement::MovementController,
-        physics::{BoxCollider, Drag, DynamicBoxBundle, Pos},
-        spawn::entities::{EntityKey, TextureAtlasLayoutEntities},
-        traits::{go::Go, jump::Jump, solid::Obstruct},
+        physics::{Aabb, BoxCollider, Drag, DynamicBoxBundle, Kinematic, PhysicsStep, Pos, Vel},
+        spawn::{
+            entities::{EntityKind, EntitySprite, RegisterEntity},
+            level::LevelEntity,
+        },
+        traits::{go::Go, jump::Jump, solid::Obstruct, stomp::Stomper},
     },
     screen::Screen,
+    AppSet,
 };
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashSet};
 use seldom_state::prelude::*;
 
+/// Name the player is registered under.
+pub const PLAYER: &str = "mario";
+
+/// Lives the player starts a game with.
+const STARTING_LIVES: u32 = 3;
+/// Upward speed of the hop the player dies with.
+const DIE_HOP: f32 = 300.;
+/// How long the player falls after dying, before respawning.
+const DYING_DURATION: Duration = Duration::from_secs(3);
+/// Players falling below this height fell into a pit.
+const FALL_DEATH_Y: f32 = -2. * TILE_SIZE as f32;
+/// Top horizontal speed of a walking player, where its acceleration and
+/// drag even out.
+const PLAYER_MAX_WALK_SPEED: f32 = 80.;
+/// Bounds of the run cycle's pace relative to walking at top speed, so it
+/// doesn't stall as the player slows down.
+const MIN_RUN_PACE: f32 = 0.25;
+const MAX_RUN_PACE: f32 = 2.5;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Lives>();
+    app.init_resource::<ReachedCheckpoint>();
+    app.add_event::<PlayerDied>();
+    app.register_entity(EntityKind {
+        name: PLAYER,
+        spawn: spawn_player,
+    });
+    app.add_systems(
+        FixedUpdate,
+        (kill_fallen_players, die, update_dying, reach_checkpoints)
+            .chain()
+            .after(PhysicsStep::SolveVelocities)
+            .run_if(in_state(Screen::Playing)),
+    );
+    app.add_systems(Update, (animate_player, flip_player).in_set(AppSet::Update));
+}
+
 #[derive(Component, Debug, Clone, PartialEq, Eq, Default, Reflect)]
 #[reflect(Component)]
 pub struct Player;
 
+/// Lives left before the game is over.
+#[derive(Resource, Debug)]
+pub struct Lives(pub u32);
+
+impl Default for Lives {
+    fn default() -> Self {
+        Self(STARTING_LIVES)
+    }
+}
+
+/// A player that died, hopping and falling out of the level.
+#[derive(Component, Debug)]
+pub struct Dying(Timer);
+
+/// Sent once the player is done dying, to respawn or end the game.
+#[derive(Event, Debug, Clone)]
+pub struct PlayerDied {
+    pub entity: Entity,
+}
+
+/// A checkpoint of the current level, reached once the player passes `x`.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Checkpoint {
+    pub index: usize,
+    pub x: f32,
+}
+
+/// Index of the furthest checkpoint of the current level the player reached,
+/// where it respawns after dying.
+#[derive(Resource, Debug, Default)]
+pub struct ReachedCheckpoint(pub usize);
+
 #[derive(Debug, Default, Clone, Reflect)]
 enum Direction {
     #[default]
@@ -52,12 +126,11 @@ pub struct Falling;
 
 pub fn spawn_player(
     commands: &mut Commands,
-    image_handles: &Res<HandleMap<TextureKey>>,
-    atlas_layout_handles: &ResMut<TextureAtlasLayoutEntities>,
-    key: EntityKey,
+    sprite: EntitySprite,
+    _entity_asset: &LevelEntityAsset,
     pos_x: u32,
     pos_y: u32,
-) {
+) -> Entity {
     let mut player_command = commands.spawn_empty();
     let player_entity = Arc::new(player_command.id());
 
@@ -101,6 +174,7 @@ pub fn spawn_player(
         move |world: &mut World| {
             let running = world.get::<Running>(*entity).unwrap();
             world.get_mut::<Drag>(*entity).unwrap().0.x = running.drag_factor_x;
+            play_clip(world, *entity, "run");
         }
     };
 
@@ -109,6 +183,7 @@ pub fn spawn_player(
         move |world: &mut World| {
             let walking = world.get::<Walking>(*entity).unwrap();
             world.get_mut::<Drag>(*entity).unwrap().0.x = walking.drag_factor_x;
+            play_clip(world, *entity, "run");
         }
     };
 
@@ -117,9 +192,20 @@ pub fn spawn_player(
         move |world: &mut World| {
             let mut jump = world.get_mut::<Jump>(*entity).unwrap();
             jump.start();
+            play_clip(world, *entity, "jump");
         }
     };
 
+    let on_idle = {
+        let entity = player_entity.clone();
+        move |world: &mut World| play_clip(world, *entity, "idle")
+    };
+
+    let on_fall = {
+        let entity = player_entity.clone();
+        move |world: &mut World| play_clip(world, *entity, "jump")
+    };
+
     let player_state = StateMachine::default()
         .trans_builder(is_walking, |_: &AnyState, _| {
             Some(Walking {
@@ -136,14 +222,16 @@ pub fn spawn_player(
         .trans::<Jumping, _>(done(Some(Done::Success)), Falling)
         .command_on_enter::<Running>(on_run)
         .command_on_enter::<Walking>(on_walk)
-        .command_on_enter::<Jumping>(on_jump);
+        .command_on_enter::<Jumping>(on_jump)
+        .command_on_enter::<Idling>(on_idle)
+        .command_on_enter::<Falling>(on_fall);
 
     player_command.insert((
-        Name::new(key.to_string().to_string()),
+        Name::new(PLAYER),
         Player,
-        PlayerAnimation::idling(),
+        Animator::new(sprite.animations, "idle"),
         SpriteBundle {
-            texture: image_handles[&TextureKey::Entities].clone_weak(),
+            texture: sprite.texture,
             transform: Transform {
                 translation: Vec3::new(pos_x as f32, pos_y as f32, 1.),
                 ..Default::default()
@@ -151,10 +239,11 @@ pub fn spawn_player(
             ..Default::default()
         },
         TextureAtlas {
-            layout: atlas_layout_handles.0[&key].clone(),
+            layout: sprite.layout,
             index: 0,
         },
         MovementController::default(),
+        PowerState::Small,
         Idling,
         player_state,
         Go {
@@ -162,7 +251,7 @@ pub fn spawn_player(
             deceleration: 300.,
             ..Default::default()
         },
-        Obstruct(false),
+        (Obstruct::default(), Stomper { bounce: 200. }),
         Jump {
             duration: 0.3,
             grace_period: 0.1,
@@ -171,7 +260,7 @@ pub fn spawn_player(
             ..Default::default()
         },
         DynamicBoxBundle {
-            pos: Pos(Vec2::new(100., 100.)),
+            pos: Pos(Vec2::new(pos_x as f32, pos_y as f32)),
             drag: Drag(Vec2::new(FAST_DRAG, 0.)),
             collider: BoxCollider {
                 size: Vec2::new(16., 16.),
@@ -179,5 +268,131 @@ pub fn spawn_player(
             ..Default::default()
         },
         StateScoped(Screen::Playing),
+        LevelEntity,
     ));
+
+    *player_entity
+}
+
+/// Plays the variant of `clip` for the player's size, unless it is in the
+/// middle of growing or shrinking.
+fn play_clip(world: &mut World, entity: Entity, clip: &str) {
+    if world.get::<PowerTransition>(entity).is_some() {
+        return;
+    }
+    let power = world.get::<PowerState>(entity).copied().unwrap_or_default();
+    if let Some(mut animator) = world.get_mut::<Animator>(entity) {
+        animator.play(&power.clip(clip));
+        animator.speed = 1.;
+    }
+}
+
+/// Refines the clip picked by the current state with the player's motion:
+/// skids when the input opposes the velocity, idles when stopped and plays
+/// the run cycle faster the faster the player goes.
+fn animate_player(
+    mut player_q: Query<
+        (&mut Animator, &Go, &Vel, &PowerState),
+        (
+            With<Player>,
+            Or<(With<Walking>, With<Running>)>,
+            Without<PowerTransition>,
+            Without<Dying>,
+        ),
+    >,
+) {
+    for (mut animator, go, vel, power) in &mut player_q {
+        let clip = if go.direction != 0 && vel.0.x * (go.direction as f32) < 0. {
+            "break"
+        } else if go.distance == 0. {
+            "idle"
+        } else {
+            "run"
+        };
+        animator.play(&power.clip(clip));
+        // The run cycle's frame length is a distance in pixels: walking at
+        // top speed advances a frame every `frame_len` pixels, and the
+        // cycle speeds up or slows down with the player from there.
+        animator.speed = if clip == "run" {
+            let pace = (vel.0.x.abs() / PLAYER_MAX_WALK_SPEED).clamp(MIN_RUN_PACE, MAX_RUN_PACE);
+            pace * PLAYER_MAX_WALK_SPEED
+        } else {
+            1.
+        };
+    }
+}
+
+fn flip_player(mut player_q: Query<(&mut Sprite, &Go), (With<Player>, Changed<Go>)>) {
+    for (mut sprite, go) in &mut player_q {
+        sprite.flip_x = go.heading < 0;
+    }
+}
+
+fn kill_fallen_players(
+    mut killed_events: EventWriter<Killed>,
+    player_q: Query<(Entity, &Pos), (With<Player>, Without<Dying>)>,
+) {
+    for (entity, pos) in &player_q {
+        if pos.0.y < FALL_DEATH_Y {
+            killed_events.send(Killed { entity });
+        }
+    }
+}
+
+/// Takes a life and the controls away from killed players, which hop up in
+/// their die frame and fall through the level.
+fn die(
+    mut commands: Commands,
+    mut lives: ResMut<Lives>,
+    mut killed_events: EventReader<Killed>,
+    mut player_q: Query<(&mut Vel, &mut Animator, &mut Visibility), (With<Player>, Without<Dying>)>,
+) {
+    let mut killed = HashSet::new();
+    for Killed { entity } in killed_events.read() {
+        let Ok((mut vel, mut animator, mut visibility)) = player_q.get_mut(*entity) else {
+            continue;
+        };
+        if !killed.insert(*entity) {
+            continue;
+        }
+        lives.0 = lives.0.saturating_sub(1);
+        vel.0 = Vec2::new(0., DIE_HOP);
+        animator.speed = 1.;
+        animator.play("die");
+        // Invincible players may have been hidden by their blinking.
+        *visibility = Visibility::Inherited;
+        // The state machine reads `Go` and `Jump`, so it goes with them.
+        // Without a collider the body is left to gravity, through the floor.
+        commands
+            .entity(*entity)
+            .remove::<(StateMachine, Go, Jump, Aabb, BoxCollider)>()
+            .remove::<(Kinematic, PowerTransition, Invincible)>()
+            .insert(Dying(Timer::new(DYING_DURATION, TimerMode::Once)));
+    }
+}
+
+fn update_dying(
+    time: Res<Time>,
+    mut died_events: EventWriter<PlayerDied>,
+    mut dying_q: Query<(Entity, &mut Dying)>,
+) {
+    for (entity, mut dying) in &mut dying_q {
+        if dying.0.tick(time.delta()).just_finished() {
+            died_events.send(PlayerDied { entity });
+        }
+    }
+}
+
+fn reach_checkpoints(
+    mut reached: ResMut<ReachedCheckpoint>,
+    player_q: Query<&Pos, (With<Player>, Without<Dying>)>,
+    checkpoint_q: Query<&Checkpoint>,
+) {
+    for pos in &player_q {
+        for checkpoint in &checkpoint_q {
+            if pos.0.x >= checkpoint.x && checkpoint.index > reached.0 {
+                reached.0 = checkpoint.index;
+            }
+        }
+    }
 }
@@ -0,0 +1,406 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    config::TILE_SIZE,
+    game::{
+        animations::animator::Animator,
+        assets::{LevelEntityAsset, SpriteSheets},
+        entities::{Dying, Falling, Jumping, Player},
+        physics::{Aabb, BoxCollider, DynamicBoxBundle, Kinematic, PhysicsStep, Pos, Vel},
+        spawn::{
+            entities::{EntityKind, EntitySprite, RegisterEntity, SpawnEntityFn},
+            level::LevelEntity,
+        },
+        tiles::components::{BlockContents, BlockOpened, XSide},
+        traits::solid::Obstruct,
+    },
+    screen::Screen,
+    AppSet,
+};
+
+const SMALL_SIZE: Vec2 = Vec2::new(16., 16.);
+const LARGE_SIZE: Vec2 = Vec2::new(16., 32.);
+const TRANSITION_DURATION: Duration = Duration::from_millis(480);
+const INVINCIBLE_DURATION: Duration = Duration::from_secs(2);
+const BLINK_DURATION: Duration = Duration::from_millis(100);
+const MUSHROOM_SPEED: f32 = 50.;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<PowerState>();
+    app.register_type::<PowerUp>();
+    app.register_type::<MushroomDirection>();
+    app.add_event::<Damaged>();
+    app.add_event::<Killed>();
+    app.register_entity(EntityKind {
+        name: "mushroom",
+        spawn: spawn_mushroom,
+    })
+    .register_entity(EntityKind {
+        name: "fire-flower",
+        spawn: spawn_fire_flower,
+    });
+
+    app.add_systems(
+        FixedUpdate,
+        (
+            move_mushrooms.in_set(PhysicsStep::PreIntegrate),
+            (
+                release_power_ups,
+                pick_up_power_ups,
+                damage_player,
+                update_power_transition,
+                update_invincible,
+            )
+                .chain()
+                .after(PhysicsStep::SolveVelocities),
+        )
+            .run_if(in_state(Screen::Playing)),
+    );
+    app.add_systems(Update, blink_invincible.in_set(AppSet::Update));
+}
+
+/// How powered up the player is.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub enum PowerState {
+    #[default]
+    Small,
+    Super,
+    /// Drawn with the super frames, the sheet has no fire palette.
+    Fire,
+}
+
+impl PowerState {
+    pub fn is_large(self) -> bool {
+        !matches!(self, PowerState::Small)
+    }
+
+    pub fn collider_size(self) -> Vec2 {
+        if self.is_large() {
+            LARGE_SIZE
+        } else {
+            SMALL_SIZE
+        }
+    }
+
+    /// Name of the variant of `clip` for this size, e.g. `"run-large"`.
+    pub fn clip(self, clip: &str) -> String {
+        if self.is_large() {
+            format!("{}-large", clip)
+        } else {
+            clip.to_string()
+        }
+    }
+
+    fn promote(self, power_up: PowerUp) -> Self {
+        match (self, power_up) {
+            (PowerState::Small, _) => PowerState::Super,
+            (_, PowerUp::FireFlower) => PowerState::Fire,
+            (power, PowerUp::Mushroom) => power,
+        }
+    }
+
+    fn demote(self) -> Option<Self> {
+        match self {
+            PowerState::Small => None,
+            PowerState::Super => Some(PowerState::Small),
+            PowerState::Fire => Some(PowerState::Super),
+        }
+    }
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub enum PowerUp {
+    Mushroom,
+    FireFlower,
+}
+
+/// Direction a mushroom slides in, reversed by the walls it runs into.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct MushroomDirection(pub f32);
+
+impl Default for MushroomDirection {
+    fn default() -> Self {
+        Self(1.)
+    }
+}
+
+/// Freezes the player while it grows or shrinks.
+#[derive(Component, Debug)]
+pub struct PowerTransition(Timer);
+
+/// Ignores damage for a while after being hurt.
+#[derive(Component, Debug)]
+pub struct Invincible(Timer);
+
+/// Sent to hurt an entity with a [`PowerState`].
+#[derive(Event, Debug, Clone)]
+pub struct Damaged {
+    pub entity: Entity,
+}
+
+/// Sent when a small player is hurt.
+#[derive(Event, Debug, Clone)]
+pub struct Killed {
+    pub entity: Entity,
+}
+
+pub fn spawn_mushroom(
+    commands: &mut Commands,
+    sprite: EntitySprite,
+    entity_asset: &LevelEntityAsset,
+    pos_x: u32,
+    pos_y: u32,
+) -> Entity {
+    commands
+        .spawn((
+            Name::new(entity_asset.name.clone()),
+            PowerUp::Mushroom,
+            MushroomDirection::default(),
+            SpriteBundle {
+                texture: sprite.texture,
+                ..Default::default()
+            },
+            TextureAtlas {
+                layout: sprite.layout,
+                index: 0,
+            },
+            DynamicBoxBundle {
+                pos: Pos(Vec2::new(pos_x as f32, pos_y as f32)),
+                collider: BoxCollider { size: SMALL_SIZE },
+                ..Default::default()
+            },
+            Obstruct::default(),
+            StateScoped(Screen::Playing),
+        ))
+        .id()
+}
+
+pub fn spawn_fire_flower(
+    commands: &mut Commands,
+    sprite: EntitySprite,
+    entity_asset: &LevelEntityAsset,
+    pos_x: u32,
+    pos_y: u32,
+) -> Entity {
+    commands
+        .spawn((
+            Name::new(entity_asset.name.clone()),
+            PowerUp::FireFlower,
+            Animator::new(sprite.animations, "bloom"),
+            SpriteBundle {
+                texture: sprite.texture,
+                ..Default::default()
+            },
+            TextureAtlas {
+                layout: sprite.layout,
+                index: 0,
+            },
+            DynamicBoxBundle {
+                pos: Pos(Vec2::new(pos_x as f32, pos_y as f32)),
+                collider: BoxCollider { size: SMALL_SIZE },
+                ..Default::default()
+            },
+            // Flowers stay on top of the block they came out of.
+            Kinematic,
+            StateScoped(Screen::Playing),
+        ))
+        .id()
+}
+
+fn move_mushrooms(
+    mut mushroom_q: Query<(&mut MushroomDirection, &Obstruct, &mut Vel), Without<Kinematic>>,
+) {
+    for (mut direction, obstruct, mut vel) in &mut mushroom_q {
+        match obstruct.x_side {
+            Some(XSide::Left) => direction.0 = -1.,
+            Some(XSide::Right) => direction.0 = 1.,
+            None => {}
+        }
+        vel.0.x = direction.0 * MUSHROOM_SPEED;
+    }
+}
+
+/// Chance blocks holding a power-up release a mushroom, or a fire flower if the player
+/// is already large. Released power-ups go with the level, like the ones it places.
+fn release_power_ups(
+    mut commands: Commands,
+    mut block_opened: EventReader<BlockOpened>,
+    sprite_sheets: Res<SpriteSheets>,
+    player_q: Query<&PowerState, With<Player>>,
+) {
+    for BlockOpened {
+        tile_pos,
+        by,
+        contents,
+        ..
+    } in block_opened.read()
+    {
+        if *contents != BlockContents::PowerUp {
+            continue;
+        }
+        let Ok(power) = player_q.get(*by) else {
+            continue;
+        };
+        let (name, spawn): (_, SpawnEntityFn) = if power.is_large() {
+            ("fire-flower", spawn_fire_flower)
+        } else {
+            ("mushroom", spawn_mushroom)
+        };
+        let power_up = spawn(
+            &mut commands,
+            EntitySprite::new(&sprite_sheets, name),
+            &LevelEntityAsset {
+                name: name.to_string(),
+                ..Default::default()
+            },
+            tile_pos.x * TILE_SIZE,
+            (tile_pos.y + 1) * TILE_SIZE,
+        );
+        commands.entity(power_up).insert(LevelEntity);
+    }
+}
+
+fn pick_up_power_ups(
+    mut commands: Commands,
+    power_up_q: Query<(Entity, &PowerUp, &Aabb)>,
+    mut player_q: Query<
+        (
+            Entity,
+            &Aabb,
+            &mut PowerState,
+            &mut BoxCollider,
+            &mut Animator,
+        ),
+        (With<Player>, Without<Dying>),
+    >,
+) {
+    for (player, player_aabb, mut power, mut collider, mut animator) in &mut player_q {
+        for (entity, power_up, aabb) in &power_up_q {
+            if !aabb.intersects(player_aabb) {
+                continue;
+            }
+            commands.entity(entity).despawn_recursive();
+            let promoted = power.promote(*power_up);
+            change_power(
+                &mut commands,
+                player,
+                &mut power,
+                &mut collider,
+                &mut animator,
+                promoted,
+            );
+        }
+    }
+}
+
+fn damage_player(
+    mut commands: Commands,
+    mut damaged_events: EventReader<Damaged>,
+    mut killed_events: EventWriter<Killed>,
+    mut player_q: Query<
+        (&mut PowerState, &mut BoxCollider, &mut Animator),
+        (Without<Invincible>, Without<PowerTransition>),
+    >,
+) {
+    for Damaged { entity } in damaged_events.read() {
+        let Ok((mut power, mut collider, mut animator)) = player_q.get_mut(*entity) else {
+            continue;
+        };
+        match power.demote() {
+            Some(demoted) => {
+                change_power(
+                    &mut commands,
+                    *entity,
+                    &mut power,
+                    &mut collider,
+                    &mut animator,
+                    demoted,
+                );
+                commands
+                    .entity(*entity)
+                    .insert(Invincible(Timer::new(INVINCIBLE_DURATION, TimerMode::Once)));
+            }
+            None => {
+                killed_events.send(Killed { entity: *entity });
+            }
+        }
+    }
+}
+
+/// Resizes the collider for `to`. `Pos` is the bottom of the collider, so
+/// the feet stay where they are. Changing size plays the grow or shrink
+/// clip while the player is frozen.
+fn change_power(
+    commands: &mut Commands,
+    entity: Entity,
+    power: &mut PowerState,
+    collider: &mut BoxCollider,
+    animator: &mut Animator,
+    to: PowerState,
+) {
+    if power.is_large() != to.is_large() {
+        animator.speed = 1.;
+        animator.play_once(if to.is_large() { "grow" } else { "shrink" });
+        commands.entity(entity).insert((
+            PowerTransition(Timer::new(TRANSITION_DURATION, TimerMode::Once)),
+            Kinematic,
+        ));
+    }
+    collider.size = to.collider_size();
+    *power = to;
+}
+
+fn update_power_transition(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut player_q: Query<(
+        Entity,
+        &mut PowerTransition,
+        &PowerState,
+        &mut Animator,
+        &mut Vel,
+        Has<Jumping>,
+        Has<Falling>,
+    )>,
+) {
+    for (entity, mut transition, power, mut animator, mut vel, jumping, falling) in &mut player_q {
+        vel.0 = Vec2::ZERO;
+        if !transition.0.tick(time.delta()).finished() {
+            continue;
+        }
+        commands
+            .entity(entity)
+            .remove::<(PowerTransition, Kinematic)>();
+        let clip = if jumping || falling { "jump" } else { "idle" };
+        animator.play(&power.clip(clip));
+    }
+}
+
+fn update_invincible(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut invincible_q: Query<(Entity, &mut Invincible, &mut Visibility)>,
+) {
+    for (entity, mut invincible, mut visibility) in &mut invincible_q {
+        if invincible.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Invincible>();
+            *visibility = Visibility::Inherited;
+        }
+    }
+}
+
+fn blink_invincible(mut invincible_q: Query<(&Invincible, &mut Visibility)>) {
+    for (invincible, mut visibility) in &mut invincible_q {
+        let blink = invincible.0.elapsed().as_millis() / BLINK_DURATION.as_millis();
+        *visibility = if blink % 2 == 0 {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
@@ -0,0 +1,64 @@
+//! The time left to finish a level, running out kills the player.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    game::{
+        entities::{Dying, Killed, Player},
+        physics::PhysicsStep,
+    },
+    screen::Screen,
+};
+
+/// Time given for each life and each level.
+const LEVEL_DURATION: Duration = Duration::from_secs(300);
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<LevelTime>();
+    app.add_systems(
+        FixedUpdate,
+        count_down
+            .after(PhysicsStep::SolveVelocities)
+            .run_if(in_state(Screen::Playing)),
+    );
+}
+
+#[derive(Resource, Debug)]
+pub struct LevelTime(Timer);
+
+impl LevelTime {
+    /// Whole seconds left, rounded up.
+    pub fn remaining_secs(&self) -> u32 {
+        self.0.remaining().as_secs_f32().ceil() as u32
+    }
+
+    /// Gives the full time back, for a new life or a new level.
+    pub fn reset(&mut self) {
+        self.0.reset();
+    }
+}
+
+impl Default for LevelTime {
+    fn default() -> Self {
+        Self(Timer::new(LEVEL_DURATION, TimerMode::Once))
+    }
+}
+
+/// Counts down while the player is alive, killing it when the time is up.
+fn count_down(
+    time: Res<Time>,
+    mut level_time: ResMut<LevelTime>,
+    mut killed_events: EventWriter<Killed>,
+    player_q: Query<Entity, (With<Player>, Without<Dying>)>,
+) {
+    if player_q.is_empty() {
+        return;
+    }
+    if level_time.0.tick(time.delta()).just_finished() {
+        for entity in &player_q {
+            killed_events.send(Killed { entity });
+        }
+    }
+}
@@ -1,40 +1,134 @@
 pub mod animations;
 pub mod assets;
 pub mod entities;
+pub mod level_time;
 pub mod movement;
 pub mod physics;
+pub mod score;
 pub mod spawn;
 pub mod tiles;
 pub mod traits;
 
 use bevy::prelude::*;
 
-use self::assets::LevelKey;
+use self::{
+    assets::{LevelKey, Levels},
+    entities::{Coins, Lives, ReachedCheckpoint},
+    score::Score,
+    spawn::{entities::validate_level_entities, map::validate_level_tiles},
+};
+use crate::screen::Screen;
+
+/// Command line argument naming the level new games start at.
+const START_LEVEL_ARG: &str = "--level";
+/// Environment variable naming the level new games start at, when there is
+/// no [`START_LEVEL_ARG`].
+const START_LEVEL_VAR: &str = "MARIO_LEVEL";
 
 #[derive(Resource, Default)]
 pub struct GameState {
     pub current_level: LevelKey,
+    /// Level new games start at.
+    pub start_level: LevelKey,
 }
 
+/// Starts a new game at the given level, with the score, coins and lives
+/// reset.
+#[derive(Event, Debug)]
+pub struct NewGame(pub LevelKey);
+
 #[derive(Resource, Default)]
 pub struct SettingsState {
     pub is_fullscreen: bool,
 }
 
 pub(super) fn plugin(app: &mut App) {
+    let start_level = start_level();
     app.insert_resource(GameState {
-        current_level: LevelKey::OneOne,
+        current_level: start_level.clone(),
+        start_level,
     });
     app.insert_resource(SettingsState {
         is_fullscreen: false,
     });
+    app.observe(new_game);
+    app.add_systems(
+        OnExit(Screen::Loading),
+        check_start_level
+            .after(validate_level_entities)
+            .after(validate_level_tiles),
+    );
 
     app.add_plugins((
         animations::plugin,
         assets::plugin,
+        level_time::plugin,
+        score::plugin,
+        spawn::plugin,
+        simulation_plugin,
+    ));
+}
+
+/// The level given with `--level <name>` or by `MARIO_LEVEL`, the first
+/// level otherwise. It is only checked once the levels are loaded, by
+/// [`check_start_level`].
+fn start_level() -> LevelKey {
+    let mut args = std::env::args().skip_while(|arg| arg != START_LEVEL_ARG);
+    let name = match (args.next(), args.next()) {
+        (Some(_), Some(name)) => Some(name),
+        (Some(_), None) => {
+            warn!("{} needs a level name", START_LEVEL_ARG);
+            std::env::var(START_LEVEL_VAR).ok()
+        }
+        (None, _) => std::env::var(START_LEVEL_VAR).ok(),
+    };
+    name.map_or_else(LevelKey::default, |name| LevelKey::from(name.as_str()))
+}
+
+/// Falls back to the first level when the start level doesn't exist.
+fn check_start_level(mut gs: ResMut<GameState>, levels: Res<Levels>) {
+    if levels.get(&gs.start_level).is_some() {
+        return;
+    }
+    let known = levels
+        .keys()
+        .iter()
+        .map(LevelKey::name)
+        .collect::<Vec<_>>()
+        .join(", ");
+    warn!(
+        "unknown start level {}, starting at {} instead. Levels are {}",
+        gs.start_level,
+        LevelKey::default(),
+        known
+    );
+    gs.start_level = LevelKey::default();
+    gs.current_level = LevelKey::default();
+}
+
+fn new_game(
+    trigger: Trigger<NewGame>,
+    mut gs: ResMut<GameState>,
+    mut lives: ResMut<Lives>,
+    mut coins: ResMut<Coins>,
+    mut score: ResMut<Score>,
+    mut reached: ResMut<ReachedCheckpoint>,
+) {
+    gs.current_level = trigger.event().0.clone();
+    *lives = Lives::default();
+    *coins = Coins::default();
+    *score = Score::default();
+    *reached = ReachedCheckpoint::default();
+}
+
+/// The input, physics and tile-collision pipeline and the entity kinds levels
+/// can place, without any asset loading or rendering. Shared by the game and
+/// [`crate::headless`].
+pub(crate) fn simulation_plugin(app: &mut App) {
+    app.add_plugins((
+        entities::plugin,
         movement::plugin,
         physics::plugin,
-        spawn::plugin,
         tiles::plugin,
         traits::plugin,
     ));
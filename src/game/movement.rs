@@ -1,26 +1,44 @@
-use bevy::prelude::*;
+use std::{fs, io, path::Path};
+
+use bevy::{prelude::*, transform::TransformSystem};
+use serde::{Deserialize, Serialize};
 
 use crate::{dev_tools::custom::DebugMode, AppSet};
 
-use super::entities::player::Player;
+use super::{
+    entities::player::{Dying, Player},
+    physics::sync_transforms,
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<MovementController>();
     app.add_systems(
         Update,
-        record_movement_controller.in_set(AppSet::RecordInput),
+        record_movement_controller
+            .in_set(AppSet::RecordInput)
+            .run_if(not(resource_exists::<InputPlayback>)),
+    );
+    app.add_systems(
+        FixedFirst,
+        (
+            play_input_tape.run_if(resource_exists::<InputPlayback>),
+            capture_input_tape.run_if(resource_exists::<InputRecorder>),
+        )
+            .chain(),
     );
 
     app.add_systems(
-        Update,
+        PostUpdate,
         camera_follow_player
+            .after(sync_transforms)
+            .before(TransformSystem::TransformPropagate)
             .run_if(resource_exists::<DebugMode>.and_then(resource_equals(DebugMode(false)))),
     );
 
     // my_system.run_if(resource_exists::<R>.and_then(resource_equals(R(0)))),
 }
 
-#[derive(Reflect, Debug, Clone)]
+#[derive(Reflect, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ControllerDirection {
     Idle,
     Left,
@@ -85,12 +103,14 @@ pub struct MovementController {
     pub moving: ControllerDirection,
     pub jumping: bool,
     pub running: bool,
+    pub down: bool,
 }
 impl MovementController {
     fn reset(&mut self) {
         self.moving = ControllerDirection::default();
         self.jumping = false;
         self.running = false;
+        self.down = false;
     }
 
     pub fn is_moving(&self) -> bool {
@@ -100,11 +120,79 @@ impl MovementController {
             ControllerDirection::Right => true,
         }
     }
+
+    fn apply(&mut self, frame: &InputFrame) {
+        self.moving = frame.moving.clone();
+        self.jumping = frame.jumping;
+        self.running = frame.running;
+        self.down = frame.down;
+    }
+}
+
+/// The [`MovementController`] state of the player during one fixed tick.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputFrame {
+    pub moving: ControllerDirection,
+    pub jumping: bool,
+    pub running: bool,
+    #[serde(default)]
+    pub down: bool,
+}
+
+impl From<&MovementController> for InputFrame {
+    fn from(controller: &MovementController) -> Self {
+        Self {
+            moving: controller.moving.clone(),
+            jumping: controller.jumping,
+            running: controller.running,
+            down: controller.down,
+        }
+    }
+}
+
+/// A recording of the player's input, one [`InputFrame`] per fixed tick.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct InputTape {
+    pub frames: Vec<InputFrame>,
+}
+
+impl InputTape {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, serde_json::to_string(self)?)
+    }
+}
+
+/// While present, every fixed tick appends the player's controller state to
+/// the tape.
+#[derive(Resource, Debug, Default)]
+pub struct InputRecorder(pub InputTape);
+
+/// While present, the player's controller is driven from the tape instead of
+/// the keyboard. Once the tape runs out the controller stays idle.
+#[derive(Resource, Debug, Default)]
+pub struct InputPlayback {
+    tape: InputTape,
+    frame: usize,
+}
+
+impl InputPlayback {
+    pub fn new(tape: InputTape) -> Self {
+        Self { tape, frame: 0 }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.tape.frames.len()
+    }
 }
 
 fn record_movement_controller(
     input: Res<ButtonInput<KeyCode>>,
-    mut controller_query: Query<&mut MovementController>,
+    mut controller_query: Query<&mut MovementController, With<Player>>,
 ) {
     for mut controller in &mut controller_query {
         controller.reset();
@@ -121,12 +209,39 @@ fn record_movement_controller(
         if input.pressed(KeyCode::ShiftLeft) {
             controller.running = true
         }
+        if input.pressed(KeyCode::ArrowDown) {
+            controller.down = true
+        }
+    }
+}
+
+fn play_input_tape(
+    mut playback: ResMut<InputPlayback>,
+    mut controller_query: Query<&mut MovementController, With<Player>>,
+) {
+    let frame = playback.tape.frames.get(playback.frame).cloned();
+    for mut controller in &mut controller_query {
+        match &frame {
+            Some(frame) => controller.apply(frame),
+            None => controller.reset(),
+        }
+    }
+    playback.frame += 1;
+}
+
+fn capture_input_tape(
+    mut recorder: ResMut<InputRecorder>,
+    controller_query: Query<&MovementController, With<Player>>,
+) {
+    for controller in &controller_query {
+        recorder.0.frames.push(controller.into());
     }
 }
 
 fn camera_follow_player(
     mut camera: Query<&mut Transform, With<Camera>>,
-    player: Query<&Transform, (With<Player>, Without<Camera>)>,
+    // The camera stays put while the player falls out of the level.
+    player: Query<&Transform, (With<Player>, Without<Dying>, Without<Camera>)>,
 ) {
     for mut transform in &mut camera {
         for player_transform in &player {
@@ -46,7 +46,7 @@ pub struct Forces(pub Vec<Vec2>);
 #[reflect(Component)]
 pub struct Drag(pub Vec2);
 
-#[derive(Component, Debug, Default, Reflect)]
+#[derive(Component, Debug, Default, Clone, Copy, Reflect)]
 #[reflect(Component)]
 pub struct Aabb {
     pub min: Vec2,
@@ -114,6 +114,12 @@ impl Default for BoxCollider {
     }
 }
 
+/// A body moved by gameplay code rather than by forces. Integration and
+/// tile obstruction leave it alone.
+#[derive(Component, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct Kinematic;
+
 #[derive(Bundle, Default)]
 pub struct DynamicBoxBundle {
     pub pos: Pos,
@@ -2,14 +2,13 @@ mod components;
 mod resources;
 mod systems;
 
-use bevy::prelude::*;
-use std::time::Duration;
+use bevy::{gizmos::config::GizmoConfigStore, prelude::*, transform::TransformSystem};
 
 use crate::config::GRAVITY;
 pub use crate::game::physics::{components::*, resources::*, systems::*};
 
-pub const DT: f32 = 1. / 60.;
-const COLLISION_PAIR_VEL_MARGIN_FACTOR: f32 = 2. * DT;
+/// How many steps ahead, in velocity terms, the broad phase looks for pairs.
+const COLLISION_PAIR_VEL_MARGIN_STEPS: f32 = 2.;
 
 #[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
 pub enum PhysicsStep {
@@ -21,7 +20,6 @@ pub enum PhysicsStep {
     PostSolvePositions,
     UpdateVelocities,
     SolveVelocities,
-    SyncTransform,
     Debug,
 }
 
@@ -34,7 +32,8 @@ pub(super) fn plugin(app: &mut App) {
         .register_type::<Restitution>()
         .register_type::<Forces>()
         .register_type::<Aabb>()
-        .register_type::<BoxCollider>();
+        .register_type::<BoxCollider>()
+        .register_type::<Kinematic>();
 
     app.configure_sets(
         FixedUpdate,
@@ -47,15 +46,20 @@ pub(super) fn plugin(app: &mut App) {
             PhysicsStep::PostSolvePositions,
             PhysicsStep::UpdateVelocities,
             PhysicsStep::SolveVelocities,
-            PhysicsStep::SyncTransform,
             PhysicsStep::Debug,
         )
             .chain(),
     );
     app.init_resource::<Contacts>();
     app.init_resource::<CollisionPairs>();
+    app.init_resource::<SpatialHash>();
     app.insert_resource(Gravity(Vec2::new(0., -GRAVITY)));
-    app.insert_resource(Time::<Fixed>::from_duration(Duration::from_secs_f32(DT)));
+    // Respect a step rate inserted before the plugin was added.
+    let step_rate = *app
+        .world_mut()
+        .get_resource_or_insert_with(StepRate::default);
+    app.insert_resource(Time::<Fixed>::from_hz(step_rate.0));
+    app.add_systems(First, apply_step_rate.run_if(resource_changed::<StepRate>));
 
     app.add_systems(
         FixedUpdate,
@@ -76,11 +80,13 @@ pub(super) fn plugin(app: &mut App) {
         (solve_vel).in_set(PhysicsStep::SolveVelocities),
     );
     app.add_systems(
-        FixedUpdate,
-        (sync_transforms).in_set(PhysicsStep::SyncTransform),
+        PostUpdate,
+        sync_transforms.before(TransformSystem::TransformPropagate),
     );
     app.add_systems(
         FixedUpdate,
-        (draw_box_collider, draw_velocity).in_set(PhysicsStep::Debug),
+        (draw_box_collider, draw_velocity)
+            .in_set(PhysicsStep::Debug)
+            .run_if(resource_exists::<GizmoConfigStore>),
     );
 }
@@ -1,4 +1,7 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
+
+use super::components::Aabb;
+use crate::config::TILE_SIZE;
 
 #[derive(Resource, Debug, Default)]
 pub struct Contacts(pub Vec<(Entity, Entity, Vec2)>);
@@ -6,6 +9,23 @@ pub struct Contacts(pub Vec<(Entity, Entity, Vec2)>);
 #[derive(Resource, Debug, Default)]
 pub(crate) struct CollisionPairs(pub Vec<(Entity, Entity)>);
 
+/// Number of physics steps per second. Drives `Time<Fixed>`, and every
+/// physics system integrates over [`StepRate::dt`].
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct StepRate(pub f64);
+
+impl StepRate {
+    pub fn dt(&self) -> f32 {
+        (1. / self.0) as f32
+    }
+}
+
+impl Default for StepRate {
+    fn default() -> Self {
+        Self(60.)
+    }
+}
+
 #[derive(Resource)]
 pub struct Gravity(pub Vec2);
 impl Default for Gravity {
@@ -13,3 +33,69 @@ impl Default for Gravity {
         Self(Vec2::new(0., -9.81))
     }
 }
+
+/// Uniform grid broad phase. Every [`Aabb`] is bucketed into the cells it
+/// covers, and only bodies sharing a cell are tested against each other.
+#[derive(Resource, Debug)]
+pub struct SpatialHash {
+    cell_size: f32,
+    bodies: Vec<(Entity, Aabb, IVec2)>,
+    cells: HashMap<IVec2, Vec<usize>>,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            bodies: Vec::new(),
+            cells: HashMap::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.bodies.clear();
+        self.cells.clear();
+    }
+
+    pub fn insert(&mut self, entity: Entity, aabb: Aabb) {
+        let min = self.cell(aabb.min);
+        let max = self.cell(aabb.max);
+        let index = self.bodies.len();
+        self.bodies.push((entity, aabb, min));
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                self.cells.entry(IVec2::new(x, y)).or_default().push(index);
+            }
+        }
+    }
+
+    /// Appends every intersecting pair to `pairs` as `(greater, lesser)`
+    /// entities, sorted so the narrow phase sees them in a stable order.
+    pub fn collect_pairs(&self, pairs: &mut Vec<(Entity, Entity)>) {
+        for (cell, indices) in self.cells.iter() {
+            for (i, &index_a) in indices.iter().enumerate() {
+                let (entity_a, aabb_a, min_a) = &self.bodies[index_a];
+                for &index_b in &indices[i + 1..] {
+                    let (entity_b, aabb_b, min_b) = &self.bodies[index_b];
+                    // Bodies spanning several cells meet in more than one of
+                    // them, only report the pair from the first shared cell.
+                    if min_a.max(*min_b) != *cell || !aabb_a.intersects(aabb_b) {
+                        continue;
+                    }
+                    pairs.push((*entity_a.max(entity_b), *entity_a.min(entity_b)));
+                }
+            }
+        }
+        pairs.sort_unstable();
+    }
+
+    fn cell(&self, point: Vec2) -> IVec2 {
+        (point / self.cell_size).floor().as_ivec2()
+    }
+}
+
+impl Default for SpatialHash {
+    fn default() -> Self {
+        Self::new(TILE_SIZE as f32)
+    }
+}
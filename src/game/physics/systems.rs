@@ -1,12 +1,19 @@
 use super::components::*;
 use super::resources::*;
-use super::COLLISION_PAIR_VEL_MARGIN_FACTOR;
-use super::DT;
+use super::COLLISION_PAIR_VEL_MARGIN_STEPS;
 use bevy::prelude::*;
 
-pub fn update_aabb_box(mut query: Query<(&mut Aabb, &Pos, &Vel, &BoxCollider)>) {
+pub fn apply_step_rate(step_rate: Res<StepRate>, mut time: ResMut<Time<Fixed>>) {
+    time.set_timestep_hz(step_rate.0);
+}
+
+pub fn update_aabb_box(
+    mut query: Query<(&mut Aabb, &Pos, &Vel, &BoxCollider)>,
+    step_rate: Res<StepRate>,
+) {
+    let margin_factor = COLLISION_PAIR_VEL_MARGIN_STEPS * step_rate.dt();
     for (mut aabb, pos, vel, rbox) in query.iter_mut() {
-        let margin = COLLISION_PAIR_VEL_MARGIN_FACTOR * vel.0.length();
+        let margin = margin_factor * vel.0.length();
         let half_extents = rbox.size / 2. + Vec2::splat(margin);
         aabb.min = pos.0 - half_extents;
         aabb.max = pos.0 + half_extents;
@@ -15,22 +22,16 @@ pub fn update_aabb_box(mut query: Query<(&mut Aabb, &Pos, &Vel, &BoxCollider)>)
 
 pub fn collect_collision_pairs(
     query: Query<(Entity, &Aabb)>,
+    mut spatial_hash: ResMut<SpatialHash>,
     mut collision_pairs: ResMut<CollisionPairs>,
 ) {
     collision_pairs.0.clear();
+    spatial_hash.clear();
 
-    unsafe {
-        for (entity_a, aabb_a) in query.iter_unsafe() {
-            for (entity_b, aabb_b) in query.iter_unsafe() {
-                if entity_a <= entity_b {
-                    continue;
-                }
-                if aabb_a.intersects(aabb_b) {
-                    collision_pairs.0.push((entity_a, entity_b));
-                }
-            }
-        }
+    for (entity, aabb) in &query {
+        spatial_hash.insert(entity, *aabb);
     }
+    spatial_hash.collect_pairs(&mut collision_pairs.0);
 }
 
 pub fn box_box(pos_a: Vec2, size_a: Vec2, pos_b: Vec2, size_b: Vec2) -> Option<Contact> {
@@ -54,17 +55,22 @@ pub fn box_box(pos_a: Vec2, size_a: Vec2, pos_b: Vec2, size_b: Vec2) -> Option<C
 }
 
 pub fn integrate(
-    mut query: Query<(
-        &mut Pos,
-        &mut PrevPos,
-        &mut Vel,
-        &mut PreSolveVel,
-        &Mass,
-        &mut Forces,
-        &Drag,
-    )>,
+    mut query: Query<
+        (
+            &mut Pos,
+            &mut PrevPos,
+            &mut Vel,
+            &mut PreSolveVel,
+            &Mass,
+            &mut Forces,
+            &Drag,
+        ),
+        Without<Kinematic>,
+    >,
     gravity: Res<Gravity>,
+    step_rate: Res<StepRate>,
 ) {
+    let dt = step_rate.dt();
     for (mut pos, mut prev_pos, mut vel, mut pre_solve_vel, mass, mut forces, drag) in
         query.iter_mut()
     {
@@ -77,7 +83,7 @@ pub fn integrate(
         forces.0.clear();
 
         // new velocity based on forces
-        vel.0 += DT * forces_sum / mass.0;
+        vel.0 += dt * forces_sum / mass.0;
 
         // add drag factor
         {
@@ -85,7 +91,7 @@ pub fn integrate(
             vel.0 -= velocity * velocity.abs() * drag.0;
         }
 
-        pos.0 += DT * vel.0;
+        pos.0 += dt * vel.0;
 
         pre_solve_vel.0 = vel.0;
     }
@@ -132,9 +138,10 @@ pub fn solve_pos(
     }
 }
 
-pub fn update_vel(mut query: Query<(&Pos, &PrevPos, &mut Vel)>) {
+pub fn update_vel(mut query: Query<(&Pos, &PrevPos, &mut Vel)>, step_rate: Res<StepRate>) {
+    let dt = step_rate.dt();
     for (pos, prev_pos, mut vel) in query.iter_mut() {
-        vel.0 = (pos.0 - prev_pos.0) / DT;
+        vel.0 = (pos.0 - prev_pos.0) / dt;
         if vel.0.x.abs() <= 0.05 {
             vel.0.x = 0.0;
         }
@@ -177,9 +184,15 @@ pub fn solve_vel(
     }
 }
 
-pub fn sync_transforms(mut query: Query<(&mut bevy::transform::components::Transform, &Pos)>) {
-    for (mut transform, pos) in query.iter_mut() {
-        transform.translation = pos.0.extend(1.);
+/// Places every body between its last two physics positions, by how far
+/// the render frame is into the next fixed step.
+pub fn sync_transforms(
+    mut query: Query<(&mut bevy::transform::components::Transform, &Pos, &PrevPos)>,
+    fixed_time: Res<Time<Fixed>>,
+) {
+    let alpha = fixed_time.overstep_fraction();
+    for (mut transform, pos, prev_pos) in query.iter_mut() {
+        transform.translation = prev_pos.0.lerp(pos.0, alpha).extend(1.);
     }
 }
 
@@ -0,0 +1,124 @@
+//! Points scored by the player, shown floating where they were scored.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    config::TILE_SIZE,
+    game::{
+        entities::CoinCollected,
+        physics::PhysicsStep,
+        spawn::level::LevelEntity,
+        tiles::components::{BlockContents, BlockOpened},
+        traits::{killable::KnockedOut, stomp::Stomped},
+    },
+    screen::Screen,
+    AppSet,
+};
+
+const STOMP_POINTS: u32 = 100;
+const COIN_POINTS: u32 = 200;
+/// Points for each mob knocked out in a row, the last repeating.
+const COMBO_POINTS: [u32; 8] = [100, 200, 400, 500, 800, 1000, 2000, 4000];
+const POPUP_SPEED: f32 = 40.;
+const POPUP_DURATION: Duration = Duration::from_millis(800);
+const POPUP_FONT_SIZE: f32 = 8.;
+/// Over the entities, which are drawn at z = 1.
+const POPUP_Z: f32 = 2.;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Score>();
+    app.add_systems(
+        FixedUpdate,
+        (score_stomps, score_knock_outs, score_coins)
+            .after(PhysicsStep::SolveVelocities)
+            .run_if(in_state(Screen::Playing)),
+    );
+    app.add_systems(Update, rise_score_popups.in_set(AppSet::Update));
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct Score(pub u32);
+
+/// Points floating up from where they were scored.
+#[derive(Component, Debug)]
+pub struct ScorePopup(Timer);
+
+fn spawn_score_popup(commands: &mut Commands, points: u32, pos: Vec2) {
+    commands.spawn((
+        Name::new("Score Popup"),
+        ScorePopup(Timer::new(POPUP_DURATION, TimerMode::Once)),
+        Text2dBundle {
+            text: Text::from_section(
+                points.to_string(),
+                TextStyle {
+                    font_size: POPUP_FONT_SIZE,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ),
+            transform: Transform::from_translation(pos.extend(POPUP_Z)),
+            ..default()
+        },
+        StateScoped(Screen::Playing),
+        LevelEntity,
+    ));
+}
+
+fn score_stomps(
+    mut commands: Commands,
+    mut score: ResMut<Score>,
+    mut stomped_events: EventReader<Stomped>,
+) {
+    for Stomped { pos, .. } in stomped_events.read() {
+        score.0 += STOMP_POINTS;
+        spawn_score_popup(&mut commands, STOMP_POINTS, *pos);
+    }
+}
+
+fn score_knock_outs(
+    mut commands: Commands,
+    mut score: ResMut<Score>,
+    mut knocked_out_events: EventReader<KnockedOut>,
+) {
+    for KnockedOut { combo, pos, .. } in knocked_out_events.read() {
+        let points = COMBO_POINTS[(*combo as usize).min(COMBO_POINTS.len() - 1)];
+        score.0 += points;
+        spawn_score_popup(&mut commands, points, *pos);
+    }
+}
+
+/// Every coin scores, only the coins knocked out of blocks show it.
+fn score_coins(
+    mut commands: Commands,
+    mut score: ResMut<Score>,
+    mut coin_collected: EventReader<CoinCollected>,
+    mut block_opened: EventReader<BlockOpened>,
+) {
+    for _ in coin_collected.read() {
+        score.0 += COIN_POINTS;
+    }
+    for BlockOpened {
+        tile_pos, contents, ..
+    } in block_opened.read()
+    {
+        if *contents == BlockContents::Coin {
+            let pos = Vec2::new(tile_pos.x as f32, (tile_pos.y + 1) as f32) * TILE_SIZE as f32;
+            spawn_score_popup(&mut commands, COIN_POINTS, pos);
+        }
+    }
+}
+
+fn rise_score_popups(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut popup_q: Query<(Entity, &mut ScorePopup, &mut Transform)>,
+) {
+    for (entity, mut popup, mut transform) in &mut popup_q {
+        transform.translation.y += POPUP_SPEED * time.delta_seconds();
+        if popup.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -1,23 +1,23 @@
 use core::fmt;
 
-use bevy::{prelude::*, utils::hashbrown::HashMap};
+use bevy::{
+    prelude::*,
+    utils::hashbrown::{HashMap, HashSet},
+};
 
 use crate::{
-    config::{FRAMES_RECT_GOOMBA_BROWN, FRAMES_RECT_PLAYER, MAP_HEIGHT, TILE_SIZE},
+    config::{MAP_HEIGHT, TILE_SIZE},
     game::{
-        assets::{HandleMap, LevelAsset, LevelKey, TextureKey},
-        entities::{spawn_goomba, spawn_player},
+        assets::{AnimationClip, LevelAsset, LevelEntityAsset, Levels, SpriteSheets},
+        entities::{Checkpoint, ReachedCheckpoint, PLAYER},
+        spawn::{level::LevelEntity, triggers::spawn_level_triggers},
         GameState,
     },
     screen::Screen,
 };
 
-pub(super) fn plugin(app: &mut App) {
-    app.add_systems(OnEnter(Screen::Loading), setup_entities);
-}
-
-#[derive(Resource)]
-pub struct TextureAtlasLayoutEntities(pub HashMap<EntityKey, Handle<TextureAtlasLayout>>);
+/// Where the player starts in levels without checkpoints, in world space.
+const DEFAULT_START: [u32; 2] = [50, 30];
 
 #[derive(Event, Debug)]
 pub struct SpawnEntities;
@@ -26,113 +26,195 @@ pub struct SpawnEntities;
 #[reflect(Component)]
 pub struct Mob {}
 
-#[derive(Hash, Default, Eq, PartialEq, Clone, Copy, Reflect)]
-pub enum EntityKey {
-    #[default]
-    Player,
-    GoombaBrown,
+#[derive(Default)]
+pub struct EntitySprite {
+    pub texture: Handle<Image>,
+    pub layout: Handle<TextureAtlasLayout>,
+    pub animations: HashMap<String, AnimationClip>,
 }
 
-impl fmt::Display for EntityKey {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            EntityKey::Player => write!(f, "Player"),
-            EntityKey::GoombaBrown => write!(f, "GoombaBrown"),
-        }
+impl EntitySprite {
+    /// Sprite of the entity kind `name`, drawn from the sprite sheet of the
+    /// same name. Kinds without a sheet get default handles.
+    pub fn new(sprite_sheets: &SpriteSheets, name: &str) -> Self {
+        sprite_sheets
+            .get(name)
+            .map(|sheet| Self {
+                texture: sheet.texture.clone_weak(),
+                layout: sheet.layout.clone(),
+                animations: sheet.animations.clone(),
+            })
+            .unwrap_or_default()
     }
 }
 
-impl From<&str> for EntityKey {
-    fn from(value: &str) -> Self {
-        match value {
-            "goomba-brown" => Self::GoombaBrown,
-            _ => Self::GoombaBrown,
-        }
+/// Spawns an entity of a registered kind at a world position.
+pub type SpawnEntityFn = fn(&mut Commands, EntitySprite, &LevelEntityAsset, u32, u32) -> Entity;
+
+/// An entity kind levels can place by name. Its sprite comes from
+/// `sprites/{name}.sprite.json`, if there is one.
+#[derive(Clone, Copy)]
+pub struct EntityKind {
+    pub name: &'static str,
+    pub spawn: SpawnEntityFn,
+}
+
+#[derive(Resource, Default)]
+pub struct EntityRegistry(HashMap<&'static str, EntityKind>);
+
+impl EntityRegistry {
+    pub fn get(&self, name: &str) -> Option<&EntityKind> {
+        self.0.get(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &EntityKind> {
+        self.0.values()
     }
 }
 
-pub struct SpawnEntityTemplate {
-    key: EntityKey,
-    x: u32,
-    y: u32,
+pub trait RegisterEntity {
+    fn register_entity(&mut self, kind: EntityKind) -> &mut Self;
 }
 
-impl SpawnEntityTemplate {
-    pub fn new(key: EntityKey, x: u32, y: u32) -> Self {
-        Self { key, x, y }
+impl RegisterEntity for App {
+    fn register_entity(&mut self, kind: EntityKind) -> &mut Self {
+        let mut registry = self
+            .world_mut()
+            .get_resource_or_insert_with(EntityRegistry::default);
+        if registry.0.insert(kind.name, kind).is_some() {
+            warn!("entity {} registered twice", kind.name);
+        }
+        self
     }
 }
 
-pub fn setup_entities(
-    mut commands: Commands,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+/// Error returned when a level places an entity no kind was registered for.
+#[derive(Debug)]
+pub struct UnknownEntityError(pub String);
+
+impl fmt::Display for UnknownEntityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown entity {}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownEntityError {}
+
+/// Leaves out the levels placing entities no kind was registered for, which
+/// would otherwise only fail once started, without a player.
+pub fn validate_level_entities(
+    mut level_handles: ResMut<Levels>,
+    asset_server: Res<AssetServer>,
+    registry: Res<EntityRegistry>,
+    levels: Res<Assets<LevelAsset>>,
 ) {
-    let layout_props = [
-        (EntityKey::Player, FRAMES_RECT_PLAYER.as_ref()),
-        (EntityKey::GoombaBrown, FRAMES_RECT_GOOMBA_BROWN.as_ref()),
-    ];
-
-    let mut atlas_layouts: HashMap<EntityKey, Handle<TextureAtlasLayout>> = HashMap::new();
-
-    for (key, frames) in layout_props {
-        let mut layout = TextureAtlasLayout::new_empty(UVec2::new(29 * 8, 29 * 8));
-        for frame_rect in frames {
-            layout.add_texture(URect::new(
-                frame_rect[0],
-                frame_rect[1],
-                frame_rect[0] + frame_rect[2],
-                frame_rect[1] + frame_rect[3],
-            ));
+    level_handles.retain(|key, handle| {
+        let Some(level) = levels.get(handle) else {
+            return true;
+        };
+        let unknown = level
+            .entities
+            .iter()
+            .map(|entity| entity.name.as_str())
+            .filter(|name| registry.get(name).is_none())
+            .collect::<HashSet<_>>();
+        if unknown.is_empty() {
+            return true;
         }
-        let handle = texture_atlas_layouts.add(layout);
-        atlas_layouts.insert(key, handle);
-    }
-    commands.insert_resource(TextureAtlasLayoutEntities(atlas_layouts));
+        let path = asset_server
+            .get_path(handle.id())
+            .map_or_else(|| key.to_string(), |path| path.to_string());
+        error!(
+            "level {} places unknown entities {:?}, leaving it out",
+            path, unknown
+        );
+        false
+    });
 }
 
 pub fn spawn_entities(
     _trigger: Trigger<SpawnEntities>,
     mut commands: Commands,
-    image_handles: Res<HandleMap<TextureKey>>,
-    level_handles: Res<HandleMap<LevelKey>>,
-    atlas_layout_handles: ResMut<TextureAtlasLayoutEntities>,
+    asset_server: Res<AssetServer>,
+    registry: Res<EntityRegistry>,
+    sprite_sheets: Res<SpriteSheets>,
+    level_handles: Res<Levels>,
     levels: ResMut<Assets<LevelAsset>>,
     gs: Res<GameState>,
+    reached: Res<ReachedCheckpoint>,
 ) {
-    let level = levels
-        .get(level_handles[&gs.current_level].clone_weak().id())
-        .unwrap();
-
-    spawn_player(
-        &mut commands,
-        &image_handles,
-        &atlas_layout_handles,
-        EntityKey::Player,
-        50,
-        30,
+    let Some(level_handle) = level_handles.get(&gs.current_level) else {
+        error!("unknown level {}", gs.current_level);
+        return;
+    };
+    let level = levels.get(level_handle.id()).unwrap();
+
+    if let Err(err) =
+        spawn_level_entities(&mut commands, level, &registry, &sprite_sheets, reached.0)
+    {
+        let path = asset_server.get_path(level_handle.id()).map_or_else(
+            || gs.current_level.name().to_string(),
+            |path| path.to_string(),
+        );
+        error!("failed to load {}: {}", path, err);
+    }
+}
+
+/// Spawns the player at the checkpoint of index `checkpoint`, the level's
+/// checkpoints and every entity listed in `level`. Nothing is spawned if the
+/// level places an entity that isn't in `registry`.
+pub fn spawn_level_entities(
+    commands: &mut Commands,
+    level: &LevelAsset,
+    registry: &EntityRegistry,
+    sprite_sheets: &SpriteSheets,
+    checkpoint: usize,
+) -> Result<(), UnknownEntityError> {
+    let kinds = level
+        .entities
+        .iter()
+        .map(|entity_asset| {
+            registry
+                .get(&entity_asset.name)
+                .ok_or_else(|| UnknownEntityError(entity_asset.name.clone()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let [start_x, start_y] = level
+        .checkpoints
+        .get(checkpoint)
+        .or(level.checkpoints.first())
+        .map_or(DEFAULT_START, |&[x, y]| [x, (MAP_HEIGHT * TILE_SIZE) - y]);
+    let player = registry.get(PLAYER).expect("the player is registered");
+    (player.spawn)(
+        commands,
+        EntitySprite::new(sprite_sheets, player.name),
+        &LevelEntityAsset::default(),
+        start_x,
+        start_y,
     );
+    for (index, &[x, _]) in level.checkpoints.iter().enumerate() {
+        commands.spawn((
+            Name::new("Checkpoint"),
+            Checkpoint { index, x: x as f32 },
+            StateScoped(Screen::Playing),
+            LevelEntity,
+        ));
+    }
 
     let enemies = level
         .entities
         .iter()
-        .map(|entity_asset| {
-            SpawnEntityTemplate::new(
-                EntityKey::from(entity_asset.name.as_ref()),
+        .zip(kinds)
+        .map(|(entity_asset, kind)| {
+            (kind.spawn)(
+                commands,
+                EntitySprite::new(sprite_sheets, kind.name),
+                entity_asset,
                 entity_asset.pos[0],
-                entity_asset.pos[1],
+                (MAP_HEIGHT * TILE_SIZE) - entity_asset.pos[1],
             )
         })
-        .map(|template| match template.key {
-            EntityKey::GoombaBrown => spawn_goomba(
-                &mut commands,
-                &image_handles,
-                &atlas_layout_handles,
-                template.key,
-                template.x,
-                (MAP_HEIGHT * TILE_SIZE) - template.y,
-            ),
-            _ => commands.spawn_empty().id(),
-        })
         .collect::<Vec<_>>();
     commands
         .spawn_empty()
@@ -141,6 +223,12 @@ pub fn spawn_entities(
                 ..Default::default()
             },
             Name::new("Enemies"),
+            StateScoped(Screen::Playing),
+            LevelEntity,
         ))
         .push_children(&enemies);
+
+    spawn_level_triggers(commands, &level.triggers);
+
+    Ok(())
 }
@@ -1,19 +1,65 @@
 use bevy::prelude::*;
 
 use super::{
-    entities::{spawn_entities, SpawnEntities},
+    entities::{spawn_entities, validate_level_entities, SpawnEntities},
     map::SpawnMap,
 };
+use crate::{
+    game::{
+        assets::{build_levels, LevelKey, Levels},
+        entities::ReachedCheckpoint,
+        GameState,
+    },
+    screen::Screen,
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.observe(spawn_level);
     app.observe(spawn_entities);
+    app.observe(change_level);
+    app.add_systems(
+        OnExit(Screen::Loading),
+        validate_level_entities.after(build_levels),
+    );
 }
 
 #[derive(Event, Debug, Default)]
 pub struct SpawnLevel;
 
+/// Replaces the current level with another one, or restarts it from the
+/// furthest checkpoint reached when given the current level.
+#[derive(Event, Debug)]
+pub struct ChangeLevel(pub LevelKey);
+
+/// Marks the root entities spawned for the current level, despawned
+/// recursively on [`ChangeLevel`].
+#[derive(Component, Debug, Default)]
+pub struct LevelEntity;
+
 fn spawn_level(_trigger: Trigger<SpawnLevel>, mut commands: Commands) {
     commands.trigger(SpawnMap);
     commands.trigger(SpawnEntities);
 }
+
+fn change_level(
+    trigger: Trigger<ChangeLevel>,
+    mut commands: Commands,
+    mut gs: ResMut<GameState>,
+    mut reached: ResMut<ReachedCheckpoint>,
+    levels: Res<Levels>,
+    level_q: Query<Entity, With<LevelEntity>>,
+) {
+    let key = &trigger.event().0;
+    if levels.get(key).is_none() {
+        error!("can't change to unknown level {}", key);
+        return;
+    }
+    for entity in &level_q {
+        commands.entity(entity).despawn_recursive();
+    }
+    if *key != gs.current_level {
+        reached.0 = 0;
+    }
+    gs.current_level = key.clone();
+    commands.trigger(SpawnLevel);
+}
@@ -1,10 +1,12 @@
-use bevy::{prelude::*, utils::HashMap};
+use core::fmt;
+
+use bevy::{prelude::*, utils::HashSet};
 use bevy_ecs_tilemap::{
     helpers::{
         filling::{fill_tilemap, fill_tilemap_rect},
         geometry::get_tilemap_center_transform,
     },
-    map::{TilemapId, TilemapSize, TilemapTexture, TilemapTileSize, TilemapType},
+    map::{TilemapGridSize, TilemapId, TilemapSize, TilemapTexture, TilemapTileSize, TilemapType},
     tiles::{TileBundle, TilePos, TileStorage, TileTextureIndex},
     TilemapBundle, TilemapPlugin,
 };
@@ -13,209 +15,404 @@ use crate::{
     config::{MAP_HEIGHT, TILE_SIZE},
     game::{
         assets::{
-            HandleMap, LevelAsset, LevelKey, LevelTileAsset, PatternAsset, PatternKey,
-            PatternTilesAsset, TextureKey,
+            build_levels, build_pattern_sheets, split_pattern_reference, LevelAsset,
+            LevelTileAsset, Levels, PatternError, PatternSheets, SpriteSheets,
+        },
+        spawn::level::LevelEntity,
+        tiles::components::{
+            AnimationTile, AnimationTileBuilder, Behaviour, BlockContents, CollisionLayer,
+            FlagPole, Tile, TileSet,
         },
-        tiles::components::{AnimationTile, AnimationTileBuilder, Tile},
         GameState,
     },
+    screen::Screen,
 };
 
 pub(super) fn plugin(app: &mut App) {
     app.add_plugins(TilemapPlugin);
     app.observe(spawn_map);
+    app.add_systems(
+        OnExit(Screen::Loading),
+        validate_level_tiles
+            .after(build_levels)
+            .after(build_pattern_sheets),
+    );
 }
 
+/// Distance between the z of consecutive layers, small enough that every
+/// layer stays behind the entities drawn at z = 1.
+const LAYER_Z_STEP: f32 = 0.1;
+
 #[derive(Event, Debug)]
 pub struct SpawnMap;
 
+/// Error returned when a level gives tiles a behaviour that doesn't exist.
+#[derive(Debug)]
+pub struct UnknownBehaviourError {
+    pub behavior: String,
+    pub style: String,
+    /// Ranges of the tiles given the behaviour, as written in the level or
+    /// pattern.
+    pub ranges: Vec<Vec<i32>>,
+    /// Pattern the tiles are part of, as `sheet/pattern`, or `None` for tiles
+    /// of the level itself.
+    pub pattern: Option<String>,
+}
+
+impl fmt::Display for UnknownBehaviourError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown behavior {} of {} tiles at {:?}",
+            self.behavior, self.style, self.ranges
+        )?;
+        if let Some(pattern) = &self.pattern {
+            write!(f, " of pattern {}", pattern)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for UnknownBehaviourError {}
+
+/// Error returned when the tiles of a level can't be spawned.
+#[derive(Debug)]
+pub enum MapError {
+    Behaviour(UnknownBehaviourError),
+    Pattern(PatternError),
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapError::Behaviour(err) => err.fmt(f),
+            MapError::Pattern(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for MapError {}
+
+impl From<UnknownBehaviourError> for MapError {
+    fn from(err: UnknownBehaviourError) -> Self {
+        MapError::Behaviour(err)
+    }
+}
+
+impl From<PatternError> for MapError {
+    fn from(err: PatternError) -> Self {
+        MapError::Pattern(err)
+    }
+}
+
+/// Leaves out the levels giving tiles a behaviour that doesn't exist or
+/// referencing patterns that can't be resolved, which would otherwise only
+/// fail once started, without a map.
+pub fn validate_level_tiles(
+    mut level_handles: ResMut<Levels>,
+    asset_server: Res<AssetServer>,
+    pattern_sheets: Res<PatternSheets>,
+    levels: Res<Assets<LevelAsset>>,
+) {
+    level_handles.retain(|key, handle| {
+        let Some(level) = levels.get(handle) else {
+            return true;
+        };
+        let Err(err) = check_level_tiles(level, &pattern_sheets) else {
+            return true;
+        };
+        let path = asset_server
+            .get_path(handle.id())
+            .map_or_else(|| key.to_string(), |path| path.to_string());
+        error!("level {} has {}, leaving it out", path, err);
+        false
+    });
+}
+
 fn spawn_map(
     _trigger: Trigger<SpawnMap>,
     mut commands: Commands,
-    level_handles: Res<HandleMap<LevelKey>>,
-    pattern_handles: Res<HandleMap<PatternKey>>,
-    textures_handles: Res<HandleMap<TextureKey>>,
+    asset_server: Res<AssetServer>,
+    level_handles: Res<Levels>,
+    pattern_sheets: Res<PatternSheets>,
+    sprite_sheets: Res<SpriteSheets>,
     levels: ResMut<Assets<LevelAsset>>,
-    patterns: ResMut<Assets<PatternAsset>>,
     gs: Res<GameState>,
 ) {
-    let texture_handle: Handle<Image> = textures_handles[&TextureKey::Tiles].clone_weak();
-    let map_size = TilemapSize { x: 212, y: 15 };
-    let mut tile_storage = TileStorage::empty(map_size);
-    let tilemap_entity = commands.spawn(Name::new("TileMap")).id();
+    let Some(level_handle) = level_handles.get(&gs.current_level) else {
+        error!("unknown level {}", gs.current_level);
+        return;
+    };
+    let level = levels.get(level_handle.id()).unwrap();
+
+    let Some(sheet) = sprite_sheets.get(&level.sprite_sheet) else {
+        error!("level uses unknown sprite sheet {}", level.sprite_sheet);
+        return;
+    };
+
+    let texture = sheet.texture.clone_weak();
+    if let Err(err) = spawn_level_map(&mut commands, level, &pattern_sheets, &sheet.tiles, texture)
+    {
+        let path = asset_server.get_path(level_handle.id()).map_or_else(
+            || gs.current_level.name().to_string(),
+            |path| path.to_string(),
+        );
+        error!("failed to load {}: {}", path, err);
+    }
+}
+
+/// Spawns one tilemap per layer of `level`, resolving its patterns from
+/// `pattern_sheets` and its styles from `tile_set`, plus the
+/// [`CollisionLayer`] used by tile collisions. Tiles of unknown styles are
+/// left out. The `behavior` of level tiles overrides the default of their
+/// style. Nothing is spawned if [`check_level_tiles`] fails.
+pub fn spawn_level_map(
+    commands: &mut Commands,
+    level: &LevelAsset,
+    pattern_sheets: &PatternSheets,
+    tile_set: &TileSet,
+    texture_handle: Handle<Image>,
+) -> Result<(), MapError> {
+    // Checked first, cyclic references would expand forever.
+    check_level_tiles(level, pattern_sheets)?;
+    let sheet = level.pattern_sheet.as_str();
+
+    let layers = level
+        .layers
+        .iter()
+        .map(|layer| {
+            let mut positions = Vec::new();
+            collect_tiles(
+                &layer.tiles,
+                pattern_sheets,
+                sheet,
+                IVec2::ZERO,
+                &mut positions,
+            )?;
+            Ok(positions
+                .into_iter()
+                .map(|(tile_pos, tile)| {
+                    let behaviour = tile.behavior.as_deref().and_then(Behaviour::from_name);
+                    (tile_pos, tile, behaviour)
+                })
+                .collect::<Vec<_>>())
+        })
+        .collect::<Result<Vec<_>, MapError>>()?;
+
+    let map_size =
+        layers
+            .iter()
+            .flatten()
+            .fold(TilemapSize { x: 1, y: 1 }, |size, (tile_pos, ..)| {
+                TilemapSize {
+                    x: size.x.max(tile_pos.x + 1),
+                    y: size.y.max(tile_pos.y + 1),
+                }
+            });
+    let tile_size = TilemapTileSize {
+        x: TILE_SIZE as f32,
+        y: TILE_SIZE as f32,
+    };
+    let grid_size: TilemapGridSize = tile_size.into();
+    let map_type = TilemapType::default();
+
     let map_entity = commands
         .spawn((
             Name::new("Map"),
             SpatialBundle {
                 ..Default::default()
             },
+            StateScoped(Screen::Playing),
+            LevelEntity,
         ))
         .id();
+    let mut collision_storage = TileStorage::empty(map_size);
+    let mut unknown_styles = HashSet::new();
 
-    let level = levels
-        .get(level_handles[&gs.current_level].clone_weak().id())
-        .unwrap();
-
-    for (index, layer) in level.layers.iter().enumerate() {
-        let layer_entity = commands
-            .spawn((
-                Name::new(format!("Layer-{}", index + 1)),
-                SpatialBundle {
-                    ..Default::default()
-                },
-            ))
+    for (index, positions) in layers.into_iter().enumerate() {
+        let tilemap_entity = commands
+            .spawn(Name::new(format!("Layer-{}", index + 1)))
             .id();
+        let mut tile_storage = TileStorage::empty(map_size);
 
-        let pattern_key = PatternKey::from(level.pattern_sheet.as_ref());
-        let pattern_handle = pattern_handles.get(&pattern_key).unwrap();
-        let patterns = &patterns
-            .get(pattern_handle.clone_weak().id())
-            .unwrap()
-            .patterns;
-
-        create_tiles(
-            &mut commands,
-            &layer.tiles,
-            patterns,
-            tilemap_entity,
-            layer_entity,
-            &mut tile_storage,
-            None,
-        );
-        commands
-            .get_entity(map_entity)
-            .unwrap()
-            .add_child(layer_entity);
+        for (tile_pos, tile, behaviour) in positions {
+            let style = tile.style.as_deref().unwrap();
+            let Some(tile) = tile_set.get(style) else {
+                if unknown_styles.insert(style) {
+                    warn!("level uses unknown tile style {}", style);
+                }
+                continue;
+            };
+
+            let mut contents = tile.name.is_chance().then_some(BlockContents::default());
+            let behaviour = match behaviour {
+                // A chance block holding a coin is still solid.
+                Some(Behaviour::Coin) if contents.is_some() => {
+                    contents = Some(BlockContents::Coin);
+                    tile.behaviour.clone()
+                }
+                Some(behaviour) => behaviour,
+                None => tile.behaviour.clone(),
+            };
+
+            let tile_entity = create_tile(commands, tile, &behaviour, tile_pos, tilemap_entity);
+            commands.entity(tilemap_entity).add_child(tile_entity);
+            tile_storage.set(&tile_pos, tile_entity);
+            // Decorations drawn over a solid tile don't clear its collision.
+            if !matches!(behaviour, Behaviour::None) {
+                collision_storage.set(&tile_pos, tile_entity);
+            }
+            if tile.name.is_flag_pole() {
+                commands.entity(tile_entity).insert(FlagPole);
+            }
+            if let Some(contents) = contents {
+                commands.entity(tile_entity).insert(contents);
+            }
+        }
+
+        commands.entity(tilemap_entity).insert(TilemapBundle {
+            grid_size,
+            map_type,
+            size: map_size,
+            storage: tile_storage,
+            texture: TilemapTexture::Single(texture_handle.clone()),
+            tile_size,
+            transform: Transform::from_xyz(0., 0., index as f32 * LAYER_Z_STEP),
+            ..Default::default()
+        });
+        commands.entity(map_entity).add_child(tilemap_entity);
     }
 
-    let tile_size = TilemapTileSize {
-        x: TILE_SIZE as f32,
-        y: TILE_SIZE as f32,
-    };
-    let grid_size = tile_size.into();
-    let map_type = TilemapType::default();
+    let collision_entity = commands
+        .spawn((
+            Name::new("CollisionLayer"),
+            CollisionLayer,
+            tile_set.clone(),
+            map_size,
+            grid_size,
+            collision_storage,
+        ))
+        .id();
+    commands.entity(map_entity).add_child(collision_entity);
 
-    commands.entity(tilemap_entity).insert(TilemapBundle {
-        grid_size,
-        map_type,
-        size: map_size,
-        storage: tile_storage,
-        texture: TilemapTexture::Single(texture_handle.clone()),
-        tile_size,
-        ..Default::default()
-    });
+    Ok(())
 }
 
-fn create_tiles(
-    commands: &mut Commands,
-    tiles: &[LevelTileAsset],
-    patterns: &HashMap<String, PatternTilesAsset>,
-    tilemap_entity: Entity,
-    layer_entity: Entity,
-    tile_storage: &mut TileStorage,
-    previous_position: Option<TilePos>,
-) {
+/// Checks that every pattern `level` references can be resolved, see
+/// [`PatternSheets::check`], and that every `behavior` given to the tiles
+/// of the level and of its patterns is a [`Behaviour`].
+pub fn check_level_tiles(
+    level: &LevelAsset,
+    pattern_sheets: &PatternSheets,
+) -> Result<(), MapError> {
+    let sheet = level.pattern_sheet.as_str();
+    let mut references = Vec::new();
+    for tile in level.layers.iter().flat_map(|layer| &layer.tiles) {
+        check_behaviour(tile, None)?;
+        if let Some(reference) = &tile.pattern {
+            pattern_sheets.check(sheet, reference)?;
+            references.push(split_pattern_reference(sheet, reference));
+        }
+    }
+
+    let mut checked = HashSet::new();
+    while let Some((sheet, name)) = references.pop() {
+        if !checked.insert((sheet, name)) {
+            continue;
+        }
+        let pattern = pattern_sheets.pattern(sheet, name)?;
+        for tile in &pattern.tiles {
+            check_behaviour(tile, Some(format!("{}/{}", sheet, name)))?;
+            if let Some(reference) = &tile.pattern {
+                references.push(split_pattern_reference(sheet, reference));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks the `behavior` of `tile`, part of `pattern` if it isn't a tile of
+/// the level itself.
+fn check_behaviour(
+    tile: &LevelTileAsset,
+    pattern: Option<String>,
+) -> Result<(), UnknownBehaviourError> {
+    match tile.behavior.as_deref() {
+        Some(behavior) if Behaviour::from_name(behavior).is_none() => Err(UnknownBehaviourError {
+            behavior: behavior.to_string(),
+            style: tile.style.clone().unwrap_or_default(),
+            ranges: tile.ranges.clone(),
+            pattern,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Expands the ranges of `tiles` and of the patterns they reference into
+/// tile positions, `sheet` being the pattern sheet of `tiles` and `offset`
+/// the position of the enclosing pattern.
+fn collect_tiles<'a>(
+    tiles: &'a [LevelTileAsset],
+    pattern_sheets: &'a PatternSheets,
+    sheet: &'a str,
+    offset: IVec2,
+    positions: &mut Vec<(TilePos, &'a LevelTileAsset)>,
+) -> Result<(), PatternError> {
     for tile in tiles {
-        let mut positions: Vec<(TilePos, &LevelTileAsset)> = Vec::with_capacity(0x64);
         for range in &tile.ranges {
-            match range[..] {
-                [x1, x2, y1, y2] => {
-                    for x in x1..x1 + x2 {
-                        for y in y1..y1 + y2 {
-                            positions.push((
-                                TilePos {
-                                    x: x as u32,
-                                    y: y as u32,
-                                },
-                                tile,
-                            ));
-                        }
-                    }
-                }
-                [x1, x2, y] => {
-                    for x in x1..x1 + x2 {
+            let cells = match range[..] {
+                [x1, x2, y1, y2] => (x1..x1 + x2)
+                    .flat_map(|x| (y1..y1 + y2).map(move |y| IVec2::new(x, y)))
+                    .collect::<Vec<_>>(),
+                [x1, x2, y] => (x1..x1 + x2).map(|x| IVec2::new(x, y)).collect(),
+                [x, y] => vec![IVec2::new(x, y)],
+                _ => Vec::new(),
+            };
+
+            for cell in cells {
+                let cell = cell + offset;
+                if tile.style.is_some() {
+                    // Levels count rows from the top, the tilemap from the bottom.
+                    let y = MAP_HEIGHT as i32 - cell.y;
+                    if cell.x >= 0 && y >= 0 {
                         positions.push((
                             TilePos {
-                                x: x as u32,
+                                x: cell.x as u32,
                                 y: y as u32,
                             },
                             tile,
                         ));
                     }
                 }
-                [x, y] => {
-                    positions.push((
-                        TilePos {
-                            x: x as u32,
-                            y: y as u32,
-                        },
-                        tile,
-                    ));
-                }
-                _ => {}
-            }
-        }
-
-        for (mut current_position, tile) in positions {
-            if let Some(previous_position) = previous_position {
-                current_position.x += previous_position.x;
-                current_position.y += previous_position.y;
-            }
-            if tile.style.is_some() {
-                current_position.y = MAP_HEIGHT - current_position.y;
-                create_tile(
-                    commands,
-                    tile,
-                    current_position,
-                    tilemap_entity,
-                    layer_entity,
-                    tile_storage,
-                );
-            }
 
-            if let Some(key) = tile.pattern.as_ref() {
-                let pattern = patterns.get(key).unwrap();
-                create_tiles(
-                    commands,
-                    &pattern.tiles,
-                    patterns,
-                    tilemap_entity,
-                    layer_entity,
-                    tile_storage,
-                    Some(current_position),
-                )
+                if let Some(reference) = tile.pattern.as_ref() {
+                    let (sheet, name) = split_pattern_reference(sheet, reference);
+                    let pattern = pattern_sheets.pattern(sheet, name)?;
+                    collect_tiles(&pattern.tiles, pattern_sheets, sheet, cell, positions)?;
+                }
             }
         }
     }
+    Ok(())
 }
 
 pub fn create_tile(
     commands: &mut Commands,
-    tile: &LevelTileAsset,
+    tile: &Tile,
+    behaviour: &Behaviour,
     tile_pos: TilePos,
     tilemap_entity: Entity,
-    layer_entity: Entity,
-    tile_storage: &mut TileStorage,
-) {
-    let tile = Tile::from(tile.style.as_ref().unwrap().as_ref());
-    let texture_index = match &tile.animation {
-        AnimationTileBuilder::Single(index) => index,
-        AnimationTileBuilder::Multiple {
-            frames,
-            frame_duration: _,
-        } => frames.first().unwrap(),
-    };
-
+) -> Entity {
     let tile_entity = commands
         .spawn((
             TileBundle {
                 position: tile_pos,
                 tilemap_id: TilemapId(tilemap_entity),
-                texture_index: TileTextureIndex(*texture_index),
+                texture_index: TileTextureIndex(tile.texture_index()),
                 ..Default::default()
             },
-            tile.name,
-            tile.behaviour,
+            tile.name.clone(),
+            behaviour.clone(),
             Name::new(format!(
                 "(name: {}, x: {}, y: {})",
                 tile.name, tile_pos.x, tile_pos.y
@@ -226,15 +423,14 @@ pub fn create_tile(
     if let AnimationTileBuilder::Multiple {
         frame_duration: frame_len,
         frames,
-    } = tile.animation
+    } = &tile.animation
     {
         commands.entity(tile_entity).insert(AnimationTile {
-            frames,
+            frames: frames.clone(),
             frame: 0,
-            timer: Timer::new(frame_len, TimerMode::Repeating),
+            timer: Timer::new(*frame_len, TimerMode::Repeating),
         });
     }
 
-    commands.entity(layer_entity).add_child(tile_entity);
-    tile_storage.set(&tile_pos, tile_entity);
+    tile_entity
 }
@@ -7,7 +7,8 @@ use bevy::prelude::*;
 pub mod entities;
 pub mod level;
 pub mod map;
+pub mod triggers;
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_plugins((level::plugin, map::plugin, entities::plugin));
+    app.add_plugins((level::plugin, map::plugin, triggers::plugin));
 }
@@ -0,0 +1,116 @@
+//! Trigger volumes placed by the level files, and the flag pole ending a
+//! level.
+
+use bevy::prelude::*;
+use bevy_ecs_tilemap::tiles::TilePos;
+
+use super::level::{ChangeLevel, LevelEntity};
+use crate::{
+    config::{MAP_HEIGHT, TILE_SIZE},
+    game::{
+        assets::{LevelKey, LevelTriggerAsset},
+        entities::Player,
+        physics::{Aabb, PhysicsStep},
+        tiles::components::FlagPole,
+    },
+    screen::Screen,
+};
+
+/// Side of the square area covered by a trigger, in pixels.
+const TRIGGER_SIZE: f32 = 64.;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        FixedUpdate,
+        (check_level_triggers, check_flag_pole)
+            .chain()
+            .after(PhysicsStep::SolveVelocities)
+            .run_if(in_state(Screen::Playing)),
+    );
+}
+
+#[derive(Debug, Clone)]
+pub enum TriggerAction {
+    Goto(LevelKey),
+}
+
+#[derive(Component, Debug, Clone)]
+pub struct LevelTrigger {
+    pub action: TriggerAction,
+    pub area: Aabb,
+}
+
+/// Spawns the trigger volumes of a level under a single root.
+pub fn spawn_level_triggers(commands: &mut Commands, triggers: &[LevelTriggerAsset]) {
+    let triggers = triggers
+        .iter()
+        .filter_map(|trigger| {
+            let action = match trigger.action.as_ref() {
+                "goto" => TriggerAction::Goto(LevelKey::from(trigger.name.as_str())),
+                action => {
+                    warn!("unknown trigger action {}", action);
+                    return None;
+                }
+            };
+            let point = Vec2::new(
+                trigger.pos[0] as f32,
+                (MAP_HEIGHT * TILE_SIZE) as f32 - trigger.pos[1] as f32,
+            );
+            let area = Aabb::from_vec_size(point, Vec2::splat(TRIGGER_SIZE));
+            Some(
+                commands
+                    .spawn((
+                        Name::new(format!("Trigger {}", trigger.name)),
+                        LevelTrigger { action, area },
+                    ))
+                    .id(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    commands
+        .spawn((
+            Name::new("Triggers"),
+            SpatialBundle {
+                ..Default::default()
+            },
+            StateScoped(Screen::Playing),
+            LevelEntity,
+        ))
+        .push_children(&triggers);
+}
+
+fn check_level_triggers(
+    mut commands: Commands,
+    trigger_q: Query<&LevelTrigger>,
+    player_q: Query<&Aabb, With<Player>>,
+) {
+    for player_aabb in &player_q {
+        for trigger in &trigger_q {
+            if !trigger.area.intersects(player_aabb) {
+                continue;
+            }
+            match &trigger.action {
+                TriggerAction::Goto(key) => commands.trigger(ChangeLevel(key.clone())),
+            }
+            return;
+        }
+    }
+}
+
+/// Reaching the flag pole completes the level.
+fn check_flag_pole(
+    mut next_screen: ResMut<NextState<Screen>>,
+    flag_q: Query<&TilePos, With<FlagPole>>,
+    player_q: Query<&Aabb, With<Player>>,
+) {
+    for player_aabb in &player_q {
+        if flag_q
+            .iter()
+            .any(|tile_pos| Aabb::from(*tile_pos).intersects(player_aabb))
+        {
+            next_screen.set(Screen::LevelComplete);
+            return;
+        }
+    }
+}
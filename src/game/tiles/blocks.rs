@@ -0,0 +1,320 @@
+use std::{f32::consts::PI, time::Duration};
+
+use bevy::{prelude::*, utils::HashSet};
+use bevy_ecs_tilemap::{
+    map::{TilemapId, TilemapTexture},
+    tiles::{TilePos, TileStorage, TileTextureIndex, TileVisible},
+};
+
+use crate::{
+    config::TILE_SIZE,
+    game::{
+        animations::animator::Animator,
+        assets::SpriteSheets,
+        entities::PowerState,
+        physics::{BoxCollider, Drag, Forces, Mass, Pos, PreSolveVel, PrevPos, Vel},
+        spawn::{
+            entities::{EntitySprite, Mob},
+            level::LevelEntity,
+        },
+        traits::killable::KnockedOut,
+    },
+    screen::Screen,
+};
+
+use super::components::{
+    AnimationTile, BlockContents, BlockHit, BlockOpened, Bumping, CollisionLayer, TileName, TileSet,
+};
+
+/// Style chance blocks turn into once opened.
+const OPENED_STYLE: &str = "metal";
+const BUMP_DURATION: Duration = Duration::from_millis(200);
+const BUMP_HEIGHT: f32 = 6.;
+/// Just under the entities, which are drawn at z = 1.
+const BUMP_Z: f32 = 0.9;
+/// Initial offset and velocity of each piece of a broken brick.
+const SHRAPNEL: [(Vec2, Vec2); 4] = [
+    (Vec2::new(-4., 4.), Vec2::new(-60., 400.)),
+    (Vec2::new(4., 4.), Vec2::new(60., 400.)),
+    (Vec2::new(-4., -4.), Vec2::new(-60., 300.)),
+    (Vec2::new(4., -4.), Vec2::new(60., 300.)),
+];
+
+/// The sprite standing in for a [`Bumping`] tile.
+#[derive(Component, Debug)]
+pub struct BlockBump {
+    tile: Entity,
+    /// Tiles per row of the tileset image.
+    columns: u32,
+    timer: Timer,
+}
+
+/// A piece of a broken brick, falling out of the level.
+#[derive(Component, Debug, Default)]
+pub struct Shrapnel;
+
+/// Knocks out the mobs standing on the blocks hit by players, and bumps the
+/// blocks that don't break.
+pub fn bump_blocks(
+    mut commands: Commands,
+    mut block_hits: EventReader<BlockHit>,
+    mut knocked_out: EventWriter<KnockedOut>,
+    images: Option<Res<Assets<Image>>>,
+    hitter_q: Query<&PowerState>,
+    mob_q: Query<(Entity, &Pos, &BoxCollider), With<Mob>>,
+    mut tile_q: Query<
+        (
+            &TileName,
+            &TileTextureIndex,
+            &mut TileVisible,
+            &TilePos,
+            &TilemapId,
+        ),
+        Without<Bumping>,
+    >,
+    tilemap_q: Query<&TilemapTexture, Without<CollisionLayer>>,
+) {
+    let mut hit = HashSet::new();
+    for block_hit in block_hits.read() {
+        let Ok(power) = hitter_q.get(block_hit.by) else {
+            continue;
+        };
+        let Ok((tile_name, texture_index, mut visible, tile_pos, tilemap_id)) =
+            tile_q.get_mut(block_hit.tile)
+        else {
+            continue;
+        };
+        if !(tile_name.is_breakable() || tile_name.is_chance()) || !hit.insert(block_hit.tile) {
+            continue;
+        }
+
+        // Tiles are drawn centred on their grid position, but collide as the
+        // square above and right of it.
+        let tile_world = Vec2::new(tile_pos.x as f32, tile_pos.y as f32) * TILE_SIZE as f32;
+        let tile_top = tile_world.y + TILE_SIZE as f32;
+        for (mob, pos, box_) in &mob_q {
+            let on_top = (pos.0.y - tile_top).abs() < 1.;
+            let over =
+                pos.0.x < tile_world.x + TILE_SIZE as f32 && pos.0.x + box_.size.x > tile_world.x;
+            if on_top && over {
+                knocked_out.send(KnockedOut {
+                    mob,
+                    by: block_hit.tile,
+                    combo: 0,
+                    pos: pos.0,
+                });
+            }
+        }
+
+        if breaks(tile_name, power) {
+            continue;
+        }
+        // Without the tileset image, as when running headless, there is
+        // nothing to draw the bump with.
+        let Ok(TilemapTexture::Single(texture)) = tilemap_q.get(tilemap_id.0) else {
+            continue;
+        };
+        let Some(image) = images.as_ref().and_then(|images| images.get(texture)) else {
+            continue;
+        };
+        let columns = image.width() / TILE_SIZE;
+        visible.0 = false;
+        commands.entity(block_hit.tile).insert(Bumping);
+        commands.spawn((
+            Name::new("Block Bump"),
+            BlockBump {
+                tile: block_hit.tile,
+                columns,
+                timer: Timer::new(BUMP_DURATION, TimerMode::Once),
+            },
+            SpriteBundle {
+                texture: texture.clone_weak(),
+                sprite: Sprite {
+                    rect: Some(tile_rect(texture_index.0, columns)),
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(tile_world.extend(BUMP_Z)),
+                ..Default::default()
+            },
+            StateScoped(Screen::Playing),
+            LevelEntity,
+        ));
+    }
+}
+
+/// Turns the chance blocks hit by players into metal and sends a
+/// [`BlockOpened`] for each.
+pub fn open_blocks(
+    mut commands: Commands,
+    mut block_hits: EventReader<BlockHit>,
+    mut block_opened: EventWriter<BlockOpened>,
+    hitter_q: Query<(), With<PowerState>>,
+    mut tile_q: Query<
+        (
+            &mut TileName,
+            &mut TileTextureIndex,
+            &TilePos,
+            Option<&mut AnimationTile>,
+            Option<&BlockContents>,
+        ),
+        Without<Bumping>,
+    >,
+    collision_q: Query<&TileSet, With<CollisionLayer>>,
+) {
+    let Ok(tile_set) = collision_q.get_single() else {
+        return;
+    };
+    for block_hit in block_hits.read() {
+        if !hitter_q.contains(block_hit.by) {
+            continue;
+        }
+        let Ok((mut tile_name, mut texture_index, tile_pos, animation, contents)) =
+            tile_q.get_mut(block_hit.tile)
+        else {
+            continue;
+        };
+        // Once opened, later hits of the same step find a metal block.
+        if !tile_name.is_chance() {
+            continue;
+        }
+
+        // Sheets without a metal tile keep drawing the block as it was.
+        *tile_name = TileName(OPENED_STYLE.to_string());
+        if let Some(metal) = tile_set.get(OPENED_STYLE) {
+            texture_index.0 = metal.texture_index();
+        }
+        // Stop the animation right away, it would otherwise still advance
+        // this step.
+        if let Some(mut animation) = animation {
+            animation.frames = vec![texture_index.0];
+            animation.frame = 0;
+        }
+        commands.entity(block_hit.tile).remove::<AnimationTile>();
+        block_opened.send(BlockOpened {
+            tile: block_hit.tile,
+            tile_pos: *tile_pos,
+            by: block_hit.by,
+            contents: contents.copied().unwrap_or_default(),
+        });
+    }
+}
+
+/// Breaks the bricks hit by large players into shrapnel.
+pub fn break_blocks(
+    mut commands: Commands,
+    mut block_hits: EventReader<BlockHit>,
+    sprite_sheets: Res<SpriteSheets>,
+    hitter_q: Query<&PowerState>,
+    tile_q: Query<(&TileName, &TilePos, &TilemapId), Without<Bumping>>,
+    mut tilemap_q: Query<&mut TileStorage, Without<CollisionLayer>>,
+    mut collision_q: Query<&mut TileStorage, With<CollisionLayer>>,
+) {
+    let Ok(mut collision_storage) = collision_q.get_single_mut() else {
+        return;
+    };
+    let mut broken = HashSet::new();
+    for block_hit in block_hits.read() {
+        let Ok(power) = hitter_q.get(block_hit.by) else {
+            continue;
+        };
+        let Ok((tile_name, tile_pos, tilemap_id)) = tile_q.get(block_hit.tile) else {
+            continue;
+        };
+        if !breaks(tile_name, power) || !broken.insert(block_hit.tile) {
+            continue;
+        }
+        let Ok(mut tile_storage) = tilemap_q.get_mut(tilemap_id.0) else {
+            continue;
+        };
+
+        tile_storage.remove(tile_pos);
+        collision_storage.remove(tile_pos);
+        commands.entity(block_hit.tile).despawn_recursive();
+        let tile_world = Vec2::new(tile_pos.x as f32, tile_pos.y as f32) * TILE_SIZE as f32;
+        for (offset, vel) in SHRAPNEL {
+            spawn_shrapnel(
+                &mut commands,
+                EntitySprite::new(&sprite_sheets, "brick-shrapnel"),
+                tile_world + offset,
+                vel,
+            );
+        }
+    }
+}
+
+/// Large players break bricks, anything else bumps them.
+fn breaks(tile_name: &TileName, power: &PowerState) -> bool {
+    tile_name.is_breakable() && power.is_large()
+}
+
+/// Area of the tile at `index` in a tileset image `columns` tiles wide.
+fn tile_rect(index: u32, columns: u32) -> Rect {
+    let corner = UVec2::new(index % columns, index / columns) * TILE_SIZE;
+    Rect::from_corners(corner.as_vec2(), (corner + TILE_SIZE).as_vec2())
+}
+
+fn spawn_shrapnel(commands: &mut Commands, sprite: EntitySprite, pos: Vec2, vel: Vec2) {
+    commands.spawn((
+        Name::new("Shrapnel"),
+        Shrapnel,
+        Animator::new(sprite.animations, "spinning-brick"),
+        SpriteBundle {
+            texture: sprite.texture,
+            transform: Transform::from_translation(pos.extend(1.)),
+            ..Default::default()
+        },
+        TextureAtlas {
+            layout: sprite.layout,
+            index: 0,
+        },
+        // A body without a collider, so it only falls.
+        (
+            Pos(pos),
+            PrevPos(pos),
+            Vel(vel),
+            PreSolveVel::default(),
+            Mass::default(),
+            Forces::default(),
+            Drag::default(),
+        ),
+        StateScoped(Screen::Playing),
+        LevelEntity,
+    ));
+}
+
+/// Moves the bump sprites up and back down, then shows their tile again.
+/// The sprites follow the texture of their tile, so an opened chance block
+/// bumps as metal.
+pub fn move_bumps(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut bump_q: Query<(Entity, &mut BlockBump, &mut Sprite, &mut Transform)>,
+    mut tile_q: Query<(&TilePos, &TileTextureIndex, &mut TileVisible), With<Bumping>>,
+) {
+    for (entity, mut bump, mut sprite, mut transform) in &mut bump_q {
+        let finished = bump.timer.tick(time.delta()).finished();
+        if let Ok((tile_pos, texture_index, mut visible)) = tile_q.get_mut(bump.tile) {
+            let rect = tile_rect(texture_index.0, bump.columns);
+            if sprite.rect != Some(rect) {
+                sprite.rect = Some(rect);
+            }
+            let height = BUMP_HEIGHT * (PI * bump.timer.fraction()).sin();
+            transform.translation.y = (tile_pos.y * TILE_SIZE) as f32 + height;
+            if finished {
+                visible.0 = true;
+                commands.entity(bump.tile).remove::<Bumping>();
+            }
+        }
+        if finished {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+pub fn remove_shrapnel(mut commands: Commands, shrapnel_q: Query<(Entity, &Pos), With<Shrapnel>>) {
+    for (entity, pos) in &shrapnel_q {
+        if pos.0.y < -2. * TILE_SIZE as f32 {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -1,30 +1,42 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
+use bevy_ecs_tilemap::tiles::TilePos;
 
-use std::time::Duration;
+use std::{fmt, time::Duration};
 
+use crate::{game::assets::SpriteAsset, AppSet};
+
+/// A tile boundary crossed by a body during the last step. Collisions of a
+/// body are reported per axis, nearest first.
 #[derive(Debug, Clone)]
 pub struct TileCollision {
     pub from: Entity,
     pub to: Entity,
     pub x_side: Option<XSide>,
     pub y_side: Option<YSide>,
+    /// Fraction of the step's movement at which the boundary was reached.
+    pub toi: f32,
 }
-use crate::AppSet;
 
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
 pub enum XSide {
     #[default]
     Left,
     Right,
 }
 
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
 pub enum YSide {
     #[default]
     Top,
     Bottom,
 }
 
+/// Marks the tile storage holding every tile with a [`Behaviour`], across
+/// all layers. It isn't rendered, only queried by tile collisions. Its
+/// entity also holds the [`TileSet`] the level was spawned from.
+#[derive(Component, Debug, Default)]
+pub struct CollisionLayer;
+
 #[derive(Default, Clone, Debug)]
 pub struct Tile {
     pub name: TileName,
@@ -41,6 +53,14 @@ impl Tile {
         }
     }
 
+    /// Index of the texture the tile is spawned with.
+    pub fn texture_index(&self) -> u32 {
+        match &self.animation {
+            AnimationTileBuilder::Single(index) => *index,
+            AnimationTileBuilder::Multiple { frames, .. } => frames[0],
+        }
+    }
+
     pub fn to_multiple(
         name: TileName,
         frames: Vec<u32>,
@@ -58,87 +78,73 @@ impl Tile {
     }
 }
 
-#[derive(Component, Reflect, Default, Clone, Copy, Debug)]
+/// Name of a tile in its sprite sheet, e.g. `"bricks"` or `"chance"`.
+#[derive(Component, Reflect, Default, Clone, Debug, PartialEq, Eq)]
+#[reflect(Component)]
+pub struct TileName(pub String);
+
+impl TileName {
+    pub fn is_flag_pole(&self) -> bool {
+        matches!(
+            self.0.as_str(),
+            "pole-green" | "pole-white" | "pole-finial-dark-grey" | "pole-finial-green"
+        )
+    }
+
+    pub fn is_chance(&self) -> bool {
+        matches!(
+            self.0.as_str(),
+            "chance" | "chance-1" | "chance-2" | "chance-3"
+        )
+    }
+
+    /// Bricks a large player breaks instead of bumping.
+    pub fn is_breakable(&self) -> bool {
+        matches!(self.0.as_str(), "bricks" | "bricks-top")
+    }
+}
+
+impl fmt::Display for TileName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Sent when a body hits a solid tile from below.
+#[derive(Event, Debug, Clone)]
+pub struct BlockHit {
+    pub tile: Entity,
+    pub by: Entity,
+}
+
+/// What comes out of a chance block when it is hit.
+#[derive(Component, Reflect, Default, Clone, Copy, Debug, PartialEq, Eq)]
 #[reflect(Component)]
-#[derive(strum_macros::Display)]
-pub enum TileName {
+pub enum BlockContents {
+    /// A mushroom, or a fire flower for a large player.
     #[default]
-    Ground,
-    Sky,
-    Chocolate,
-    Bricks,
-    BricksTop,
-    Metal,
-    Chance,
-    Chance1,
-    Chance2,
-    Chance3,
+    PowerUp,
     Coin,
-    Coin1,
-    Coin2,
-    Coin3,
-    PipeInsertVertLeft,
-    PipeInsertVertRight,
-    PipeVertLeft,
-    PipeVertRight,
-    PipeChromeInsertVertLeft,
-    PipeChromeInsertVertRight,
-    PipeChromeVertLeft,
-    PipeChromeVertRight,
-    PipeInsertHorTop,
-    PipeInsertHorBottom,
-    PipeHorTop,
-    PipeHorBottom,
-    PipeConnHorTop,
-    PipeConnHorBottom,
-    Cloud,
-    Cloud11,
-    Cloud12,
-    Cloud13,
-    Cloud21,
-    Cloud22,
-    Cloud23,
-    Cannon1,
-    Cannon2,
-    Cannon3,
-    Bush1,
-    Bush2,
-    Bush3,
-    GrassLeft,
-    Grass,
-    GrassRight,
-    Dirt,
-    Black,
-    LightBLue,
-    CastleTopClosed,
-    CastleTopOpen,
-    CastleWindowRight,
-    CastleArch,
-    CastleWindowLeft,
-    PoleGreen,
-    PoleWhite,
-    PoleFinialDarkGrey,
-    PoleFinialGreen,
-    HillLeft,
-    HillRight,
-    HillTop,
-    HillStainsRight,
-    HillStainsLeft,
-    Green,
-    TreeLargeTop,
-    TreeLargeBottom,
-    TreeSmall,
-    TreeWhiteLargeTop,
-    TreeWhiteLargeBottom,
-    TreeWhiteSmall,
-    TreeTrunk,
-    Fence,
-    Bridge,
-    BridgeRailGreen,
-    BridgeRailWhite,
-    Waves,
 }
 
+/// Sent when a chance block is hit, after it turned into metal. Its
+/// contents come out above `tile_pos`.
+#[derive(Event, Debug, Clone)]
+pub struct BlockOpened {
+    pub tile: Entity,
+    pub tile_pos: TilePos,
+    pub by: Entity,
+    pub contents: BlockContents,
+}
+
+/// A tile hidden while a sprite of it bumps up and down in its place.
+#[derive(Component, Debug, Default)]
+pub struct Bumping;
+
+/// Marks the tiles of a flag pole, reaching one completes the level.
+#[derive(Component, Debug, Default)]
+pub struct FlagPole;
+
 #[derive(Clone, Debug)]
 pub enum AnimationTileBuilder {
     Single(u32),
@@ -172,6 +178,18 @@ pub enum Behaviour {
 }
 
 impl Behaviour {
+    /// Looks a behaviour up by the name used in sprite sheets and levels,
+    /// e.g. `"ground"`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(Behaviour::None),
+            "ground" => Some(Behaviour::Ground),
+            "brick" => Some(Behaviour::Brick),
+            "coin" => Some(Behaviour::Coin),
+            _ => None,
+        }
+    }
+
     pub fn is_solid(&self) -> bool {
         match self {
             Behaviour::None => false,
@@ -182,116 +200,76 @@ impl Behaviour {
     }
 }
 
-impl From<&str> for Tile {
-    fn from(value: &str) -> Self {
-        match value {
-            "ground" => Tile::to_single(TileName::Ground, 0, Behaviour::Ground),
-            "sky" => Tile::to_single(TileName::Sky, 126, Behaviour::None),
-            "chocolate" => Tile::to_single(TileName::Chocolate, 3, Behaviour::None),
-            "bricks" => Tile::to_single(TileName::Bricks, 1, Behaviour::Brick),
-            "bricks-top" => Tile::to_single(TileName::BricksTop, 14, Behaviour::Brick),
-            "metal" => Tile::to_single(TileName::Metal, 2, Behaviour::Brick),
-            "chance" => Tile::to_multiple(
-                TileName::Chance,
-                [4, 5, 6].into(),
-                Duration::from_millis(200),
-                Behaviour::None,
-            ),
-            "coin" => Tile::to_multiple(
-                TileName::Chance,
-                [15, 31, 47].into(),
-                Duration::from_millis(200),
-                Behaviour::None,
-            ),
-            "pipe-insert-vert-left" => {
-                Tile::to_single(TileName::PipeInsertVertLeft, 80, Behaviour::Ground)
-            }
-            "pipe-insert-vert-right" => {
-                Tile::to_single(TileName::PipeInsertVertRight, 81, Behaviour::Ground)
-            }
-            "pipe-vert-left" => Tile::to_single(TileName::PipeVertLeft, 96, Behaviour::Ground),
-            "pipe-vert-right" => Tile::to_single(TileName::PipeVertRight, 97, Behaviour::Ground),
-            "pipe-chrome-insert-vert-left" => {
-                Tile::to_single(TileName::PipeChromeInsertVertLeft, 50, Behaviour::Ground)
-            }
-            "pipe-chrome-insert-vert-right" => {
-                Tile::to_single(TileName::PipeChromeInsertVertRight, 51, Behaviour::Ground)
-            }
-            "pipe-chrome-vert-left" => {
-                Tile::to_single(TileName::PipeChromeVertLeft, 66, Behaviour::Ground)
-            }
-            "pipe-chrome-vert-right" => {
-                Tile::to_single(TileName::PipeChromeVertRight, 67, Behaviour::Ground)
-            }
-            "pipe-insert-hor-top" => {
-                Tile::to_single(TileName::PipeInsertHorTop, 54, Behaviour::Ground)
-            }
-            "pipe-insert-hor-bottom" => {
-                Tile::to_single(TileName::PipeInsertHorBottom, 70, Behaviour::Ground)
-            }
-            "pipe-hor-top" => Tile::to_single(TileName::PipeHorTop, 54, Behaviour::Ground),
-            "pipe-hor-bottom" => Tile::to_single(TileName::PipeHorBottom, 70, Behaviour::Ground),
-            "pipe-conn-hor-top" => Tile::to_single(TileName::PipeConnHorTop, 56, Behaviour::Ground),
-            "pipe-conn-hor-bottom" => {
-                Tile::to_single(TileName::PipeConnHorBottom, 71, Behaviour::Ground)
-            }
-            "cloud-tile" => Tile::to_single(TileName::Cloud, 142, Behaviour::None),
-            "cloud-1-1" => Tile::to_single(TileName::Cloud11, 171, Behaviour::None),
-            "cloud-1-2" => Tile::to_single(TileName::Cloud12, 172, Behaviour::None),
-            "cloud-1-3" => Tile::to_single(TileName::Cloud13, 173, Behaviour::None),
-            "cloud-2-1" => Tile::to_single(TileName::Cloud21, 187, Behaviour::None),
-            "cloud-2-2" => Tile::to_single(TileName::Cloud22, 188, Behaviour::None),
-            "cloud-2-3" => Tile::to_single(TileName::Cloud23, 189, Behaviour::None),
-            "cannon-1" => Tile::to_single(TileName::Cannon1, 62, Behaviour::None),
-            "cannon-2" => Tile::to_single(TileName::Cannon2, 78, Behaviour::None),
-            "cannon-3" => Tile::to_single(TileName::Cannon3, 94, Behaviour::None),
-            "bush-1" => Tile::to_single(TileName::Bush1, 203, Behaviour::None),
-            "bush-2" => Tile::to_single(TileName::Bush2, 204, Behaviour::None),
-            "bush-3" => Tile::to_single(TileName::Bush3, 205, Behaviour::None),
-            "grass-left" => Tile::to_single(TileName::GrassLeft, 41, Behaviour::None),
-            "grass" => Tile::to_single(TileName::Grass, 42, Behaviour::None),
-            "grass-right" => Tile::to_single(TileName::GrassRight, 43, Behaviour::None),
-            "dirt" => Tile::to_single(TileName::Dirt, 13, Behaviour::None),
-            "tile-black" => Tile::to_single(TileName::Black, 125, Behaviour::None),
-            "tile-light-blue" => Tile::to_single(TileName::LightBLue, 122, Behaviour::None),
-            "castle-top-closed" => Tile::to_single(TileName::CastleTopClosed, 8, Behaviour::None),
-            "castle-top-open" => Tile::to_single(TileName::CastleTopOpen, 9, Behaviour::None),
-            "castle-window-right" => {
-                Tile::to_single(TileName::CastleWindowRight, 10, Behaviour::None)
-            }
-            "castle-arch" => Tile::to_single(TileName::CastleArch, 11, Behaviour::None),
-            "castle-window-left" => {
-                Tile::to_single(TileName::CastleWindowLeft, 12, Behaviour::None)
-            }
-            "pole-green" => Tile::to_single(TileName::PoleGreen, 220, Behaviour::None),
-            "pole-white" => Tile::to_single(TileName::PoleWhite, 219, Behaviour::None),
-            "pole-finial-dark-grey" => {
-                Tile::to_single(TileName::PoleFinialDarkGrey, 135, Behaviour::None)
-            }
-            "pole-finial-green" => Tile::to_single(TileName::PoleFinialGreen, 136, Behaviour::None),
-            "hill-left" => Tile::to_single(TileName::HillLeft, 116, Behaviour::None),
-            "hill-right" => Tile::to_single(TileName::HillRight, 118, Behaviour::None),
-            "hill-top" => Tile::to_single(TileName::HillTop, 119, Behaviour::None),
-            "hill-stains-right" => Tile::to_single(TileName::HillStainsRight, 117, Behaviour::None),
-            "hill-stains-left" => Tile::to_single(TileName::HillStainsLeft, 115, Behaviour::None),
-            "tile-green" => Tile::to_single(TileName::Green, 124, Behaviour::None),
-            "tree-large-top" => Tile::to_single(TileName::TreeLargeTop, 90, Behaviour::None),
-            "tree-large-bottom" => Tile::to_single(TileName::TreeLargeBottom, 106, Behaviour::None),
-            "tree-small" => Tile::to_single(TileName::TreeSmall, 92, Behaviour::None),
-            "tree-white-large-top" => {
-                Tile::to_single(TileName::TreeWhiteLargeTop, 91, Behaviour::None)
-            }
-            "tree-white-large-bottom" => {
-                Tile::to_single(TileName::TreeWhiteLargeBottom, 107, Behaviour::None)
+/// The tiles of a tile sprite sheet by style: one per named tile, and one
+/// per animation, which is played by the tile and takes precedence over a
+/// tile of the same name.
+#[derive(Component, Default, Clone, Debug)]
+pub struct TileSet {
+    tiles: HashMap<String, Tile>,
+}
+
+impl TileSet {
+    /// Builds the tiles of the sheet `name`, whose image is `columns` tiles
+    /// wide. Tiles and animations without a `behavior` are not solid.
+    pub fn from_sprite(name: &str, sprite: &SpriteAsset, columns: u32) -> Self {
+        let indices = sprite
+            .tiles
+            .iter()
+            .filter_map(|tile| {
+                let [x, y] = tile.index?;
+                Some((tile.name.as_str(), y * columns + x))
+            })
+            .collect::<HashMap<_, _>>();
+
+        let mut tiles = HashMap::new();
+        for tile in &sprite.tiles {
+            let Some(&index) = indices.get(tile.name.as_str()) else {
+                continue;
+            };
+            let behaviour = sheet_behaviour(name, &tile.name, tile.behavior.as_deref());
+            tiles.insert(
+                tile.name.clone(),
+                Tile::to_single(TileName(tile.name.clone()), index, behaviour),
+            );
+        }
+        for animation in &sprite.animations {
+            // Missing frames are already reported with the sprite sheet.
+            let frames = animation
+                .frames
+                .iter()
+                .filter_map(|frame| indices.get(frame.as_str()).copied())
+                .collect::<Vec<_>>();
+            if frames.is_empty() {
+                continue;
             }
-            "tree-white-small" => Tile::to_single(TileName::TreeWhiteSmall, 93, Behaviour::None),
-            "tree-trunk" => Tile::to_single(TileName::TreeTrunk, 108, Behaviour::None),
-            "fence" => Tile::to_single(TileName::Fence, 110, Behaviour::None),
-            "bridge" => Tile::to_single(TileName::Bridge, 109, Behaviour::None),
-            "bridge-rail-green" => Tile::to_single(TileName::BridgeRailGreen, 190, Behaviour::None),
-            "bridge-rail-white" => Tile::to_single(TileName::BridgeRailWhite, 174, Behaviour::None),
-            "waves" => Tile::to_single(TileName::Waves, 112, Behaviour::None),
-            _ => Tile::to_single(TileName::Waves, 112, Behaviour::None),
+            let behaviour = sheet_behaviour(name, &animation.name, animation.behavior.as_deref());
+            tiles.insert(
+                animation.name.clone(),
+                Tile::to_multiple(
+                    TileName(animation.name.clone()),
+                    frames,
+                    Duration::from_secs_f32(animation.frame_len),
+                    behaviour,
+                ),
+            );
         }
+        Self { tiles }
+    }
+
+    pub fn get(&self, style: &str) -> Option<&Tile> {
+        self.tiles.get(style)
     }
 }
+
+fn sheet_behaviour(sheet: &str, style: &str, behavior: Option<&str>) -> Behaviour {
+    let Some(behavior) = behavior else {
+        return Behaviour::None;
+    };
+    Behaviour::from_name(behavior).unwrap_or_else(|| {
+        warn!(
+            "tile {} of {} has unknown behavior {}",
+            style, sheet, behavior
+        );
+        Behaviour::None
+    })
+}
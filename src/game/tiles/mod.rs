@@ -1,3 +1,4 @@
+pub mod blocks;
 pub mod components;
 pub mod resources;
 pub mod systems;
@@ -8,6 +9,7 @@ use bevy::prelude::*;
 use super::physics::PhysicsStep;
 use crate::AppSet;
 
+use self::blocks::*;
 use self::components::*;
 use self::resources::*;
 use self::systems::*;
@@ -16,8 +18,11 @@ pub(super) fn plugin(app: &mut App) {
     app.register_type::<TileName>();
     app.register_type::<AnimationTile>();
     app.register_type::<Behaviour>();
+    app.register_type::<BlockContents>();
 
     app.init_resource::<TileCollisions>();
+    app.add_event::<BlockHit>();
+    app.add_event::<BlockOpened>();
 
     app.add_systems(
         FixedUpdate,
@@ -25,6 +30,17 @@ pub(super) fn plugin(app: &mut App) {
             update_animation_tile_timer.in_set(AppSet::TickTimers),
             update_animation_time_atlas.in_set(AppSet::Update),
             update_tile_collisions_resource.in_set(PhysicsStep::PostSolvePositions),
+            detect_block_hits
+                .in_set(PhysicsStep::PostSolvePositions)
+                .after(update_tile_collisions_resource),
+            (
+                // A bumped block is only marked `Bumping` once all three
+                // ran, so it can still be opened.
+                (bump_blocks, open_blocks, break_blocks).chain_ignore_deferred(),
+                remove_shrapnel,
+            )
+                .after(PhysicsStep::SolveVelocities),
         ),
     );
+    app.add_systems(Update, move_bumps.in_set(AppSet::Update));
 }
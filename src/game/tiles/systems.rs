@@ -1,10 +1,10 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashSet};
 use bevy_ecs_tilemap::{
     map::{TilemapGridSize, TilemapSize},
     tiles::{TileStorage, TileTextureIndex},
 };
 
-use crate::game::physics::{Aabb, BoxCollider, Pos, PrevPos};
+use crate::game::physics::{BoxCollider, Pos, PrevPos};
 
 use super::{components::*, resources::*, utils::*};
 
@@ -27,92 +27,66 @@ pub fn update_animation_time_atlas(mut query: Query<(&AnimationTile, &mut TileTe
 
 pub fn update_tile_collisions_resource(
     mut tile_collisions: ResMut<TileCollisions>,
-    tilemap_q: Query<(&TilemapSize, &TilemapGridSize, &TileStorage)>,
+    tilemap_q: Query<(&TilemapSize, &TilemapGridSize, &TileStorage), With<CollisionLayer>>,
     box_q: Query<(Entity, &Pos, &PrevPos, &BoxCollider)>,
 ) {
-    let (map_size, grid_size, tile_storage) = tilemap_q.get_single().unwrap();
-
     tile_collisions.0.clear();
 
-    for (box_entity, current_pos, prev_pos, box_) in box_q.iter() {
-        let mut pos = prev_pos.0;
-        pos.y = current_pos.0.y;
+    let Ok((map_size, grid_size, tile_storage)) = tilemap_q.get_single() else {
+        return;
+    };
 
-        let y_sides = get_tile_y_side_coords(prev_pos.0, pos, box_).map(|tile_coords| {
-            tile_coords
-                .into_iter()
-                .filter_map(|tile_coord| from_world_pos(&tile_coord, map_size, grid_size))
-                .filter_map(|tile_pos| {
-                    tile_storage
-                        .get(&tile_pos)
-                        .map(|entity| (entity, Aabb::from(tile_pos)))
-                })
-                .filter_map(|(entity, tile_aabb)| {
-                    let prev_aabb = Aabb::from_vec_size(prev_pos.0, box_.size);
-                    let current_aabb = Aabb::from_vec_size(pos, box_.size);
-                    if prev_aabb.top() <= tile_aabb.bottom()
-                        && current_aabb.top() > tile_aabb.bottom()
-                    {
-                        Some((entity, YSide::Bottom))
-                    } else if prev_aabb.bottom() >= tile_aabb.top()
-                        && current_aabb.bottom() < tile_aabb.top()
-                    {
-                        Some((entity, YSide::Top))
-                    } else {
-                        None
-                    }
-                })
-                .map(|y_side| TileCollision {
+    for (box_entity, current_pos, prev_pos, box_) in box_q.iter() {
+        let y_sides = sweep_y(prev_pos.0, current_pos.0.y, box_, map_size, grid_size)
+            .into_iter()
+            .filter_map(|(tile_pos, y_side, toi)| {
+                tile_storage.get(&tile_pos).map(|entity| TileCollision {
                     from: box_entity,
-                    to: y_side.0,
+                    to: entity,
                     x_side: None,
-                    y_side: Some(y_side.1),
+                    y_side: Some(y_side),
+                    toi,
                 })
-                .collect::<Vec<_>>()
-        });
-
-        if let Some(mut y_sides) = y_sides {
-            tile_collisions.0.append(&mut y_sides)
-        }
-
-        let mut pos = prev_pos.0;
-        pos.x = current_pos.0.x;
+            });
+        tile_collisions.0.extend(y_sides);
 
-        let x_sides = get_tile_x_side_coords(prev_pos.0, pos, box_).map(|tiles_coord| {
-            tiles_coord
-                .into_iter()
-                .filter_map(|tile_coord| from_world_pos(&tile_coord, map_size, grid_size))
-                .filter_map(|tile_pos| {
-                    tile_storage
-                        .get(&tile_pos)
-                        .map(|entity| (entity, Aabb::from(tile_pos)))
-                })
-                .filter_map(|(entity, tile_aabb)| {
-                    let prev_aabb = Aabb::from_vec_size(prev_pos.0, box_.size);
-                    let current_aabb = Aabb::from_vec_size(pos, box_.size);
-                    if prev_aabb.right() <= tile_aabb.left()
-                        && current_aabb.right() > tile_aabb.left()
-                    {
-                        Some((entity, XSide::Left))
-                    } else if prev_aabb.left() >= tile_aabb.right()
-                        && current_aabb.left() < tile_aabb.right()
-                    {
-                        Some((entity, XSide::Right))
-                    } else {
-                        None
-                    }
-                })
-                .map(|x_side| TileCollision {
+        let x_sides = sweep_x(prev_pos.0, current_pos.0.x, box_, map_size, grid_size)
+            .into_iter()
+            .filter_map(|(tile_pos, x_side, toi)| {
+                tile_storage.get(&tile_pos).map(|entity| TileCollision {
                     from: box_entity,
-                    to: x_side.0,
-                    x_side: Some(x_side.1),
+                    to: entity,
+                    x_side: Some(x_side),
                     y_side: None,
+                    toi,
                 })
-                .collect::<Vec<_>>()
-        });
+            });
+        tile_collisions.0.extend(x_sides);
+    }
+}
 
-        if let Some(mut x_sides) = x_sides {
-            tile_collisions.0.append(&mut x_sides)
+/// Sends a [`BlockHit`] for the first solid tile each body hit from below.
+pub fn detect_block_hits(
+    tile_collisions: Res<TileCollisions>,
+    behaviour_q: Query<&Behaviour>,
+    mut block_hits: EventWriter<BlockHit>,
+) {
+    let mut hit = HashSet::new();
+    for tile_collision in &tile_collisions.0 {
+        if !matches!(tile_collision.y_side, Some(YSide::Bottom)) {
+            continue;
+        }
+        if !behaviour_q
+            .get(tile_collision.to)
+            .is_ok_and(Behaviour::is_solid)
+        {
+            continue;
+        }
+        if hit.insert(tile_collision.from) {
+            block_hits.send(BlockHit {
+                tile: tile_collision.to,
+                by: tile_collision.from,
+            });
         }
     }
 }
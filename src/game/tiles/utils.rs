@@ -1,3 +1,5 @@
+use std::ops::RangeInclusive;
+
 use bevy::math::Vec2;
 use bevy_ecs_tilemap::{
     map::{TilemapGridSize, TilemapSize},
@@ -6,42 +8,99 @@ use bevy_ecs_tilemap::{
 
 use crate::game::physics::BoxCollider;
 
-pub fn get_tile_x_side_coords(
+use super::components::{XSide, YSide};
+
+/// Tiles whose bottom or top boundary is crossed by a box moving vertically
+/// from `prev_pos` to `current_y`, nearest first, with the fraction of the
+/// move at which each boundary is reached.
+pub fn sweep_y(
     prev_pos: Vec2,
-    current_pos: Vec2,
+    current_y: f32,
     box_: &BoxCollider,
-) -> Option<[Vec2; 2]> {
-    if prev_pos.x > current_pos.x {
-        let left_up = Vec2::new(current_pos.x, current_pos.y + box_.size.y);
-        let left_down = Vec2::new(current_pos.x, current_pos.y);
-        Some([left_up, left_down])
-    } else if prev_pos.x < current_pos.x {
-        let right_up = Vec2::new(current_pos.x + box_.size.x, current_pos.y + box_.size.y);
-        let right_down = Vec2::new(current_pos.x + box_.size.x, current_pos.y);
-        Some([right_up, right_down])
+    map_size: &TilemapSize,
+    grid_size: &TilemapGridSize,
+) -> Vec<(TilePos, YSide, f32)> {
+    let (start, end, side) = if current_y > prev_pos.y {
+        (
+            prev_pos.y + box_.size.y,
+            current_y + box_.size.y,
+            YSide::Bottom,
+        )
     } else {
-        None
-    }
+        (prev_pos.y, current_y, YSide::Top)
+    };
+    let columns = covered_cells(prev_pos.x, prev_pos.x + box_.size.x, grid_size.x);
+
+    crossed_cells(start, end, grid_size.y)
+        .into_iter()
+        .flat_map(|(row, toi)| {
+            columns.clone().filter_map(move |column| {
+                TilePos::from_i32_pair(column, row, map_size).map(|tile_pos| (tile_pos, side, toi))
+            })
+        })
+        .collect()
 }
 
-pub fn get_tile_y_side_coords(
+/// Tiles whose left or right boundary is crossed by a box moving
+/// horizontally from `prev_pos` to `current_x`, nearest first, with the
+/// fraction of the move at which each boundary is reached.
+pub fn sweep_x(
     prev_pos: Vec2,
-    current_pos: Vec2,
+    current_x: f32,
     box_: &BoxCollider,
-) -> Option<[Vec2; 2]> {
-    if prev_pos.y > current_pos.y {
-        let left_down = Vec2::new(current_pos.x, current_pos.y);
-        let right_down = Vec2::new(current_pos.x + box_.size.x, current_pos.y);
-        Some([left_down, right_down])
-    } else if prev_pos.y < current_pos.y {
-        let left_up = Vec2::new(current_pos.x, current_pos.y + box_.size.y);
-        let right_up = Vec2::new(current_pos.x + box_.size.x, current_pos.y + box_.size.y);
-        Some([left_up, right_up])
+    map_size: &TilemapSize,
+    grid_size: &TilemapGridSize,
+) -> Vec<(TilePos, XSide, f32)> {
+    let (start, end, side) = if current_x > prev_pos.x {
+        (
+            prev_pos.x + box_.size.x,
+            current_x + box_.size.x,
+            XSide::Left,
+        )
     } else {
-        None
+        (prev_pos.x, current_x, XSide::Right)
+    };
+    let rows = covered_cells(prev_pos.y, prev_pos.y + box_.size.y, grid_size.y);
+
+    crossed_cells(start, end, grid_size.x)
+        .into_iter()
+        .flat_map(|(column, toi)| {
+            rows.clone().filter_map(move |row| {
+                TilePos::from_i32_pair(column, row, map_size).map(|tile_pos| (tile_pos, side, toi))
+            })
+        })
+        .collect()
+}
+
+/// Cells entered by an edge moving from `start` to `end` along one axis,
+/// nearest first, with the time of impact of each in `[0, 1)`.
+fn crossed_cells(start: f32, end: f32, cell_size: f32) -> Vec<(i32, f32)> {
+    let distance = end - start;
+    if distance > 0. {
+        // Cell `i` is entered through its lower boundary `i * cell_size`.
+        let first = (start / cell_size).ceil() as i32;
+        let last = (end / cell_size).ceil() as i32 - 1;
+        (first..=last)
+            .map(|i| (i, (i as f32 * cell_size - start) / distance))
+            .collect()
+    } else if distance < 0. {
+        // Cell `i` is entered through its upper boundary `(i + 1) * cell_size`.
+        let first = (start / cell_size).floor() as i32 - 1;
+        let last = (end / cell_size).floor() as i32;
+        (last..=first)
+            .rev()
+            .map(|i| (i, ((i + 1) as f32 * cell_size - start) / distance))
+            .collect()
+    } else {
+        Vec::new()
     }
 }
 
+/// Cells overlapped by the span `[min, max)`.
+pub fn covered_cells(min: f32, max: f32, cell_size: f32) -> RangeInclusive<i32> {
+    (min / cell_size).floor() as i32..=(max / cell_size).ceil() as i32 - 1
+}
+
 pub fn from_world_pos(
     world_pos: &Vec2,
     map_size: &TilemapSize,
@@ -51,3 +110,58 @@ pub fn from_world_pos(
     let y = (world_pos.y / grid_size.y).floor() as i32;
     TilePos::from_i32_pair(x, y, map_size)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crossed_cells_moving_up_are_nearest_first() {
+        assert_eq!(
+            crossed_cells(10., 40., 16.),
+            vec![(1, 6. / 30.), (2, 22. / 30.)]
+        );
+    }
+
+    #[test]
+    fn crossed_cells_moving_down_are_nearest_first() {
+        assert_eq!(
+            crossed_cells(40., 10., 16.),
+            vec![(1, 8. / 30.), (0, 24. / 30.)]
+        );
+    }
+
+    #[test]
+    fn crossed_cells_include_a_boundary_touched_at_the_start() {
+        assert_eq!(crossed_cells(16., 20., 16.), vec![(1, 0.)]);
+        assert!(crossed_cells(16., 16., 16.).is_empty());
+    }
+
+    #[test]
+    fn sweep_x_hits_the_left_side_of_the_columns_entered() {
+        let box_ = BoxCollider {
+            size: Vec2::splat(16.),
+        };
+        let map_size = TilemapSize { x: 10, y: 10 };
+        let grid_size = TilemapGridSize { x: 16., y: 16. };
+
+        assert_eq!(
+            sweep_x(Vec2::ZERO, 20., &box_, &map_size, &grid_size),
+            vec![
+                (TilePos { x: 1, y: 0 }, XSide::Left, 0.),
+                (TilePos { x: 2, y: 0 }, XSide::Left, 0.8),
+            ]
+        );
+    }
+
+    #[test]
+    fn sweep_x_skips_columns_outside_the_map() {
+        let box_ = BoxCollider {
+            size: Vec2::splat(16.),
+        };
+        let map_size = TilemapSize { x: 10, y: 10 };
+        let grid_size = TilemapGridSize { x: 16., y: 16. };
+
+        assert!(sweep_x(Vec2::ZERO, -20., &box_, &map_size, &grid_size).is_empty());
+    }
+}
@@ -2,7 +2,7 @@ use bevy::prelude::*;
 
 use crate::game::{
     movement::MovementController,
-    physics::{Forces, Vel},
+    physics::{Forces, Kinematic, Vel},
 };
 
 #[derive(Debug, Default, Component, Reflect)]
@@ -17,11 +17,14 @@ pub struct Go {
 
 pub fn update(
     time: Res<Time>,
-    mut query: Query<(&mut Go, &MovementController, &Vel, &mut Forces)>,
+    mut query: Query<(&mut Go, &MovementController, &Vel, &mut Forces), Without<Kinematic>>,
 ) {
     let dt = time.delta().as_secs_f32();
     for (mut go, controller, vel, mut forces) in &mut query {
         go.direction = controller.moving.clone().into();
+        if go.direction != 0 {
+            go.heading = go.direction;
+        }
         let abs_x = vel.0.x.abs();
 
         if go.direction != 0 {
@@ -0,0 +1,58 @@
+use bevy::prelude::*;
+
+use crate::{
+    config::TILE_SIZE,
+    game::{
+        physics::{Aabb, BoxCollider, Pos, Vel},
+        spawn::entities::Mob,
+        traits::{go::Go, patrol::Patrol},
+    },
+};
+
+/// Upward speed a knocked out mob is tossed with before it falls off screen.
+const KNOCK_OUT_HOP: f32 = 250.;
+
+/// Sent to kill a mob outright, as opposed to stomping it.
+#[derive(Event, Debug, Clone)]
+pub struct KnockedOut {
+    pub mob: Entity,
+    pub by: Entity,
+    /// How many mobs `by` knocked out in a row before this one.
+    pub combo: u32,
+    /// Where the mob was knocked out, in world space.
+    pub pos: Vec2,
+}
+
+/// A knocked out mob, falling through the level upside down.
+#[derive(Debug, Default, Component, Reflect)]
+#[reflect(Component)]
+pub struct Dead;
+
+pub fn knock_out(
+    mut commands: Commands,
+    mut knocked_out_events: EventReader<KnockedOut>,
+    mut mob_q: Query<(&mut Vel, Option<&mut Sprite>), Without<Dead>>,
+) {
+    for KnockedOut { mob, .. } in knocked_out_events.read() {
+        let Ok((mut vel, sprite)) = mob_q.get_mut(*mob) else {
+            continue;
+        };
+        vel.0 = Vec2::new(0., KNOCK_OUT_HOP);
+        if let Some(mut sprite) = sprite {
+            sprite.flip_y = true;
+        }
+        // Without a collider the body is left to gravity, through the floor.
+        commands
+            .entity(*mob)
+            .remove::<(Mob, Patrol, Go, Aabb, BoxCollider)>()
+            .insert(Dead);
+    }
+}
+
+pub fn remove_dead(mut commands: Commands, dead_q: Query<(Entity, &Pos), With<Dead>>) {
+    for (entity, pos) in &dead_q {
+        if pos.0.y < -2. * TILE_SIZE as f32 {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -1,15 +1,31 @@
 pub mod go;
 pub mod jump;
+pub mod killable;
+pub mod patrol;
 pub mod solid;
+pub mod stomp;
 
 use bevy::prelude::*;
 
-use self::go::Go;
+use self::{
+    go::Go,
+    killable::{Dead, KnockedOut},
+    patrol::Patrol,
+    stomp::{Stomped, Stomper},
+};
 
-use super::{physics::PhysicsStep, tiles::systems::update_tile_collisions_resource};
+use super::{
+    physics::{solve_vel, PhysicsStep},
+    tiles::systems::update_tile_collisions_resource,
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<Go>();
+    app.register_type::<Dead>();
+    app.register_type::<Patrol>();
+    app.register_type::<Stomper>();
+    app.add_event::<KnockedOut>();
+    app.add_event::<Stomped>();
     app.add_systems(FixedPostUpdate, ((go::update, jump::update),).chain());
 
     app.add_systems(
@@ -19,4 +35,25 @@ pub(super) fn plugin(app: &mut App) {
             .in_set(PhysicsStep::PostSolvePositions)
             .after(update_tile_collisions_resource),
     );
+
+    app.add_systems(
+        FixedUpdate,
+        (patrol::wake, patrol::turn, patrol::steer)
+            .chain()
+            .in_set(PhysicsStep::PreIntegrate),
+    );
+
+    app.add_systems(
+        FixedUpdate,
+        stomp::resolve_contacts
+            .in_set(PhysicsStep::SolveVelocities)
+            .after(solve_vel),
+    );
+
+    app.add_systems(
+        FixedUpdate,
+        (killable::knock_out, killable::remove_dead)
+            .chain()
+            .after(PhysicsStep::SolveVelocities),
+    );
 }
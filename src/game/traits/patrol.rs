@@ -0,0 +1,123 @@
+use bevy::prelude::*;
+use bevy_ecs_tilemap::{
+    map::TilemapSize,
+    tiles::{TilePos, TileStorage},
+};
+
+use crate::{
+    config::{MOB_WAKE_DISTANCE, TILE_SIZE},
+    game::{
+        movement::{ControllerDirection, MovementController},
+        physics::{Aabb, Contacts, Pos, Vel},
+        spawn::entities::Mob,
+        tiles::components::{Behaviour, CollisionLayer, XSide, YSide},
+        traits::solid::Obstruct,
+    },
+};
+
+/// Walks a mob back and forth by steering its [`MovementController`], which
+/// [`Go`](super::go::Go) turns into forces like it does for the player.
+#[derive(Debug, Component, Reflect)]
+#[reflect(Component)]
+pub struct Patrol {
+    /// Top horizontal speed.
+    pub speed: f32,
+    /// How close the camera has to get before the mob starts walking.
+    pub wake_distance: f32,
+    /// Turn around at the edge of a platform instead of walking off it.
+    pub turn_at_ledges: bool,
+    pub direction: i32, // [-1, 1]
+    pub awake: bool,
+}
+
+impl Default for Patrol {
+    fn default() -> Self {
+        Self {
+            speed: 30.,
+            wake_distance: MOB_WAKE_DISTANCE,
+            turn_at_ledges: false,
+            direction: -1,
+            awake: false,
+        }
+    }
+}
+
+pub fn wake(
+    camera_q: Query<&GlobalTransform, With<Camera>>,
+    mut patrol_q: Query<(&mut Patrol, &Pos)>,
+) {
+    for (mut patrol, pos) in &mut patrol_q {
+        if patrol.awake {
+            continue;
+        }
+        patrol.awake = camera_q
+            .iter()
+            .any(|camera| (camera.translation().x - pos.0.x).abs() <= patrol.wake_distance);
+    }
+}
+
+/// Turns patrolling mobs around when a solid tile stopped them, when they
+/// bumped into another mob side on, or at ledges for those that avoid them.
+pub fn turn(
+    contacts: Res<Contacts>,
+    tilemap_q: Query<(&TilemapSize, &TileStorage), With<CollisionLayer>>,
+    behaviour_q: Query<&Behaviour>,
+    mob_q: Query<(), With<Mob>>,
+    mut patrol_q: Query<(&mut Patrol, &Aabb, &Obstruct)>,
+) {
+    for (mut patrol, aabb, obstruct) in &mut patrol_q {
+        match obstruct.x_side {
+            Some(XSide::Left) => patrol.direction = -1,
+            Some(XSide::Right) => patrol.direction = 1,
+            None => {}
+        }
+
+        if !patrol.turn_at_ledges || obstruct.y_side != Some(YSide::Top) {
+            continue;
+        }
+        let Ok((map_size, tile_storage)) = tilemap_q.get_single() else {
+            continue;
+        };
+        // The tile under the leading edge of the body.
+        let front_x = if patrol.direction > 0 {
+            aabb.max.x
+        } else {
+            aabb.min.x
+        };
+        let below = (Vec2::new(front_x, aabb.min.y - 1.) / TILE_SIZE as f32).floor();
+        let ground = TilePos::from_i32_pair(below.x as i32, below.y as i32, map_size)
+            .and_then(|tile_pos| tile_storage.get(&tile_pos))
+            .and_then(|tile| behaviour_q.get(tile).ok())
+            .is_some_and(Behaviour::is_solid);
+        if !ground {
+            patrol.direction = -patrol.direction;
+        }
+    }
+
+    // The contact normal points from the first body to the second, so side on
+    // both walk away from each other. Other bodies, like the player, don't
+    // turn mobs around.
+    for &(entity_a, entity_b, normal) in &contacts.0 {
+        if normal.x == 0. || !mob_q.contains(entity_a) || !mob_q.contains(entity_b) {
+            continue;
+        }
+        if let Ok((mut patrol, ..)) = patrol_q.get_mut(entity_a) {
+            patrol.direction = -normal.x.signum() as i32;
+        }
+        if let Ok((mut patrol, ..)) = patrol_q.get_mut(entity_b) {
+            patrol.direction = normal.x.signum() as i32;
+        }
+    }
+}
+
+/// Feeds the patrol direction to the controller and caps the walking speed.
+pub fn steer(mut patrol_q: Query<(&Patrol, &mut MovementController, &mut Vel)>) {
+    for (patrol, mut controller, mut vel) in &mut patrol_q {
+        controller.moving = match (patrol.awake, patrol.direction.signum()) {
+            (false, _) | (_, 0) => ControllerDirection::Idle,
+            (true, 1) => ControllerDirection::Right,
+            (true, _) => ControllerDirection::Left,
+        };
+        vel.0.x = vel.0.x.clamp(-patrol.speed, patrol.speed);
+    }
+}
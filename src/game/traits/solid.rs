@@ -1,31 +1,56 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashSet};
 use bevy_ecs_tilemap::tiles::TilePos;
 
 use crate::game::{
-    physics::{Aabb, BoxCollider, Pos},
+    physics::{Aabb, BoxCollider, Kinematic, Pos},
     tiles::{
         components::{Behaviour, XSide, YSide},
         resources::TileCollisions,
     },
 };
 
+/// Which sides of the body were stopped by a solid tile during the last
+/// step, as seen from the tile.
 #[derive(Debug, Default, Component, Reflect)]
 #[reflect(Component)]
-pub struct Obstruct(pub bool);
+pub struct Obstruct {
+    pub x_side: Option<XSide>,
+    pub y_side: Option<YSide>,
+}
+
+impl Obstruct {
+    pub fn is_obstructed(&self) -> bool {
+        self.x_side.is_some() || self.y_side.is_some()
+    }
+}
 
 pub fn obstruct(
     tile_collisions: Res<TileCollisions>,
-    mut box_q: Query<(&BoxCollider, &mut Pos, &mut Obstruct)>,
+    mut box_q: Query<(&BoxCollider, &mut Pos, &mut Obstruct), Without<Kinematic>>,
     tilemap_q: Query<(&TilePos, &Behaviour)>,
 ) {
-    for tile_collision in &tile_collisions.0 {
+    for (_, _, mut obstruct) in &mut box_q {
+        *obstruct = Obstruct::default();
+    }
+
+    // Only the nearest solid tile on each axis stops the body, even when it
+    // moved across several tiles.
+    let mut collisions = tile_collisions.0.iter().collect::<Vec<_>>();
+    collisions.sort_by(|a, b| a.toi.total_cmp(&b.toi));
+    let mut resolved_x = HashSet::new();
+    let mut resolved_y = HashSet::new();
+
+    for tile_collision in collisions {
         if let Ok((box_, mut pos, mut obstruct)) = box_q.get_mut(tile_collision.from) {
             let (tile_pos, tile_behaviour) = tilemap_q.get(tile_collision.to).unwrap();
             let tile_aabb = Aabb::from(*tile_pos);
 
-            obstruct.0 = false;
             if tile_behaviour.is_solid() {
                 if let Some(x_side) = tile_collision.x_side {
+                    if !resolved_x.insert(tile_collision.from) {
+                        continue;
+                    }
+                    obstruct.x_side = Some(x_side);
                     match x_side {
                         XSide::Left => {
                             pos.0.x = tile_aabb.left() - box_.size.x - 0.001;
@@ -36,6 +61,10 @@ pub fn obstruct(
                     }
                 }
                 if let Some(y_side) = tile_collision.y_side {
+                    if !resolved_y.insert(tile_collision.from) {
+                        continue;
+                    }
+                    obstruct.y_side = Some(y_side);
                     match y_side {
                         YSide::Top => {
                             pos.0.y = tile_aabb.top();
@@ -45,9 +74,6 @@ pub fn obstruct(
                         }
                     }
                 }
-                if tile_collision.x_side.is_some() || tile_collision.y_side.is_some() {
-                    obstruct.0 = true;
-                }
             }
         }
     }
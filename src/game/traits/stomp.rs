@@ -0,0 +1,66 @@
+use bevy::{prelude::*, utils::HashSet};
+
+use crate::game::{
+    entities::Damaged,
+    physics::{Contacts, Pos, PreSolveVel, Vel},
+    spawn::entities::Mob,
+};
+
+/// A body that flattens mobs by landing on them and is hurt when it runs
+/// into them from the side.
+#[derive(Debug, Default, Component, Reflect)]
+#[reflect(Component)]
+pub struct Stomper {
+    /// Upward speed given after a stomp.
+    pub bounce: f32,
+}
+
+/// Sent when a [`Stomper`] lands on a [`Mob`]. Each kind of mob decides
+/// what being stomped does to it.
+#[derive(Event, Debug, Clone)]
+pub struct Stomped {
+    pub stomper: Entity,
+    pub mob: Entity,
+    /// Where the mob was stomped, in world space.
+    pub pos: Vec2,
+}
+
+/// Turns the body contacts between stompers and mobs into [`Stomped`] and
+/// [`Damaged`] events. A stomp needs the mob below the stomper and the
+/// stomper falling faster than the mob, anything else hurts the stomper.
+pub fn resolve_contacts(
+    contacts: Res<Contacts>,
+    mut stomper_q: Query<(&Stomper, &PreSolveVel, &mut Vel), Without<Mob>>,
+    mob_q: Query<(&Pos, &PreSolveVel), With<Mob>>,
+    mut stomped_events: EventWriter<Stomped>,
+    mut damaged_events: EventWriter<Damaged>,
+) {
+    let mut resolved = HashSet::new();
+    for &(entity_a, entity_b, normal) in &contacts.0 {
+        // The contact normal points from the first body to the second.
+        let (stomper, mob, normal) = if stomper_q.contains(entity_a) {
+            (entity_a, entity_b, normal)
+        } else {
+            (entity_b, entity_a, -normal)
+        };
+        let (Ok((stomp, stomper_vel, mut vel)), Ok((mob_pos, mob_vel))) =
+            (stomper_q.get_mut(stomper), mob_q.get(mob))
+        else {
+            continue;
+        };
+        if !resolved.insert(mob) {
+            continue;
+        }
+
+        if normal.y < 0. && stomper_vel.0.y < mob_vel.0.y {
+            vel.0.y = stomp.bounce;
+            stomped_events.send(Stomped {
+                stomper,
+                mob,
+                pos: mob_pos.0,
+            });
+        } else {
+            damaged_events.send(Damaged { entity: stomper });
+        }
+    }
+}
@@ -0,0 +1,245 @@
+//! Runs the physics and tile-collision pipeline without a window or renderer.
+//!
+//! [`Simulation`] builds an [`App`] on top of [`MinimalPlugins`], loads level
+//! files straight from disk and advances exactly one `FixedUpdate` step per
+//! call to [`Simulation::step`], which makes runs reproducible on machines
+//! without a GPU.
+
+use std::{
+    fmt, fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use bevy::{ecs::world::CommandQueue, input::InputPlugin, prelude::*, time::TimeUpdateStrategy};
+use seldom_state::StateMachinePlugin;
+
+use crate::{
+    game::{
+        self,
+        assets::{LevelAsset, PatternAsset, PatternSheets, SpriteAsset, SpriteSheets, TextureKey},
+        entities::{Player, ReachedCheckpoint},
+        movement::{InputPlayback, InputTape},
+        physics::Pos,
+        spawn::{
+            entities::{spawn_level_entities, EntityRegistry, UnknownEntityError},
+            map::{spawn_level_map, MapError},
+        },
+        tiles::components::TileSet,
+    },
+    AppSet,
+};
+
+/// Bytes every PNG file starts with.
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+const PATTERNS_DIR: &str = "patterns";
+const PATTERN_SHEET_SUFFIX: &str = ".pattern.json";
+
+/// Error returned when a level or one of its pattern or sprite sheets can't
+/// be loaded.
+#[derive(Debug)]
+pub enum LoadError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, serde_json::Error),
+    Entity(PathBuf, UnknownEntityError),
+    Map(PathBuf, MapError),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(path, err) => write!(f, "failed to read {}: {}", path.display(), err),
+            LoadError::Parse(path, err) => {
+                write!(f, "failed to parse {}: {}", path.display(), err)
+            }
+            LoadError::Entity(path, err) => write!(f, "failed to load {}: {}", path.display(), err),
+            LoadError::Map(path, err) => write!(f, "failed to load {}: {}", path.display(), err),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+/// Plugins needed to run the simulation without `DefaultPlugins`.
+pub struct HeadlessPlugin;
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.configure_sets(
+            Update,
+            (AppSet::TickTimers, AppSet::RecordInput, AppSet::Update).chain(),
+        );
+
+        app.add_plugins((MinimalPlugins, InputPlugin, StateMachinePlugin));
+        app.add_plugins(game::simulation_plugin);
+
+        // No sprite sheet is loaded, entities get default texture handles.
+        app.insert_resource(SpriteSheets::default());
+    }
+}
+
+/// A headless game world that can be stepped one fixed tick at a time.
+pub struct Simulation {
+    app: App,
+    assets_dir: PathBuf,
+}
+
+impl Simulation {
+    /// Creates a simulation reading level, pattern and sprite files from
+    /// `assets_dir`.
+    pub fn new(assets_dir: impl Into<PathBuf>) -> Self {
+        let mut app = App::new();
+        app.add_plugins(HeadlessPlugin);
+        app.finish();
+        app.cleanup();
+
+        // Advance virtual time by exactly one fixed timestep per update, so
+        // every `App::update` runs `FixedUpdate` once.
+        let timestep = app.world().resource::<Time<Fixed>>().timestep();
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(timestep));
+        // The first update only initializes the clocks.
+        app.update();
+
+        Self {
+            app,
+            assets_dir: assets_dir.into(),
+        }
+    }
+
+    /// Loads `path`, relative to the assets directory, and spawns its
+    /// tilemap and entities. Nothing is spawned if loading fails.
+    pub fn load_level(&mut self, path: impl AsRef<Path>) -> Result<(), LoadError> {
+        let level: LevelAsset = self.read_json(path.as_ref())?;
+        let pattern_sheets = self.read_pattern_sheets()?;
+        let sprite_path = Path::new("sprites").join(format!("{}.sprite.json", level.sprite_sheet));
+        let sprite: SpriteAsset = self.read_json(&sprite_path)?;
+        let tile_set = self.read_tile_set(&level.sprite_sheet, &sprite)?;
+
+        let world = self.app.world_mut();
+        let mut queue = CommandQueue::default();
+        {
+            let mut commands = Commands::new(&mut queue, world);
+            spawn_level_map(
+                &mut commands,
+                &level,
+                &pattern_sheets,
+                &tile_set,
+                Handle::default(),
+            )
+            .map_err(|err| LoadError::Map(self.assets_dir.join(path.as_ref()), err))?;
+            spawn_level_entities(
+                &mut commands,
+                &level,
+                world.resource::<EntityRegistry>(),
+                world.resource::<SpriteSheets>(),
+                world.resource::<ReachedCheckpoint>().0,
+            )
+            .map_err(|err| LoadError::Entity(self.assets_dir.join(path.as_ref()), err))?;
+        }
+        queue.apply(world);
+
+        Ok(())
+    }
+
+    /// Runs `steps` fixed timesteps.
+    pub fn step(&mut self, steps: u32) {
+        for _ in 0..steps {
+            self.app.update();
+        }
+    }
+
+    /// Drives the player from `tape` instead of the keyboard, one frame per
+    /// fixed step.
+    pub fn play_tape(&mut self, tape: InputTape) {
+        self.app.insert_resource(InputPlayback::new(tape));
+    }
+
+    /// Whether the tape given to [`Simulation::play_tape`] has been fully played.
+    pub fn tape_finished(&self) -> bool {
+        self.app
+            .world()
+            .get_resource::<InputPlayback>()
+            .map_or(true, InputPlayback::is_finished)
+    }
+
+    /// Position of the player, if one has been spawned.
+    pub fn player_pos(&mut self) -> Option<Vec2> {
+        let world = self.app.world_mut();
+        let mut query = world.query_filtered::<&Pos, With<Player>>();
+        query.get_single(world).ok().map(|pos| pos.0)
+    }
+
+    /// Duration of a single fixed step.
+    pub fn timestep(&self) -> Duration {
+        self.app.world().resource::<Time<Fixed>>().timestep()
+    }
+
+    pub fn world(&self) -> &World {
+        self.app.world()
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        self.app.world_mut()
+    }
+
+    /// Reads every pattern sheet, patterns can reference the patterns of
+    /// other sheets.
+    fn read_pattern_sheets(&self) -> Result<PatternSheets, LoadError> {
+        let dir = self.assets_dir.join(PATTERNS_DIR);
+        let entries = fs::read_dir(&dir).map_err(|err| LoadError::Io(dir.clone(), err))?;
+        let mut pattern_sheets = PatternSheets::default();
+        for entry in entries {
+            let entry = entry.map_err(|err| LoadError::Io(dir.clone(), err))?;
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            if !file_name.ends_with(PATTERN_SHEET_SUFFIX) {
+                continue;
+            }
+            let sheet: PatternAsset = self.read_json(&Path::new(PATTERNS_DIR).join(&file_name))?;
+            // Named the way levels name them, e.g. `overworld.pattern`.
+            pattern_sheets.insert(file_name.trim_end_matches(".json"), sheet);
+        }
+        Ok(pattern_sheets)
+    }
+
+    /// Builds the tiles of the sprite sheet `name` as the game does, from
+    /// the width of its image.
+    fn read_tile_set(&self, name: &str, sprite: &SpriteAsset) -> Result<TileSet, LoadError> {
+        let texture_key = TextureKey::from_image_url(&sprite.image_url);
+        if texture_key != Some(TextureKey::Tiles) || sprite.tile_w == 0 {
+            warn!("sprite sheet {} has no tiles", name);
+            return Ok(TileSet::default());
+        }
+        let width = self.read_image_width(Path::new(TextureKey::Tiles.path()))?;
+        Ok(TileSet::from_sprite(name, sprite, width / sprite.tile_w))
+    }
+
+    /// Reads the width of the PNG image at `path` from its header, without
+    /// decoding the image.
+    fn read_image_width(&self, path: &Path) -> Result<u32, LoadError> {
+        let path = self.assets_dir.join(path);
+        let mut header = [0; 24];
+        fs::File::open(&path)
+            .and_then(|mut file| file.read_exact(&mut header))
+            .map_err(|err| LoadError::Io(path.clone(), err))?;
+        if header[..8] != PNG_SIGNATURE {
+            let err = io::Error::new(io::ErrorKind::InvalidData, "not a PNG image");
+            return Err(LoadError::Io(path, err));
+        }
+        // The IHDR chunk comes first, starting with the width.
+        Ok(u32::from_be_bytes([
+            header[16], header[17], header[18], header[19],
+        ]))
+    }
+
+    fn read_json<T: serde::de::DeserializeOwned>(&self, path: &Path) -> Result<T, LoadError> {
+        let path = self.assets_dir.join(path);
+        let contents = fs::read_to_string(&path).map_err(|err| LoadError::Io(path.clone(), err))?;
+        serde_json::from_str(&contents).map_err(|err| LoadError::Parse(path, err))
+    }
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Self::new("assets")
+    }
+}
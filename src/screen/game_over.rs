@@ -0,0 +1,55 @@
+//! Shown once the player has no life left.
+
+use bevy::prelude::*;
+
+use super::Screen;
+use crate::{
+    game::{GameState, NewGame},
+    ui::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<GameOverAction>();
+    app.add_systems(OnEnter(Screen::GameOver), enter_game_over);
+    app.add_systems(
+        Update,
+        handle_game_over_action.run_if(in_state(Screen::GameOver)),
+    );
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+enum GameOverAction {
+    Retry,
+    Title,
+}
+
+fn enter_game_over(mut commands: Commands) {
+    commands
+        .ui_root()
+        .insert(StateScoped(Screen::GameOver))
+        .with_children(|children| {
+            children.header("Game Over");
+            children.button("Retry").insert(GameOverAction::Retry);
+            children.button("Title").insert(GameOverAction::Title);
+        });
+}
+
+fn handle_game_over_action(
+    mut commands: Commands,
+    gs: Res<GameState>,
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut button_query: InteractionQuery<&GameOverAction>,
+) {
+    for (interaction, action) in &mut button_query {
+        if matches!(interaction, Interaction::Pressed) {
+            match action {
+                GameOverAction::Retry => {
+                    commands.trigger(NewGame(gs.start_level.clone()));
+                    next_screen.set(Screen::Playing);
+                }
+                GameOverAction::Title => next_screen.set(Screen::Title),
+            }
+        }
+    }
+}
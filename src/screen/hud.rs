@@ -0,0 +1,79 @@
+//! The HUD drawn over the level while playing.
+
+use bevy::prelude::*;
+
+use super::Screen;
+use crate::{
+    game::{
+        entities::{Coins, Lives},
+        level_time::LevelTime,
+        score::Score,
+        GameState,
+    },
+    ui::prelude::*,
+    AppSet,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::Playing), spawn_hud);
+    app.add_systems(
+        Update,
+        update_hud
+            .in_set(AppSet::Update)
+            .run_if(in_state(Screen::Playing)),
+    );
+}
+
+/// What a text of the HUD shows.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum HudField {
+    Score,
+    Coins,
+    World,
+    Time,
+    Lives,
+}
+
+impl HudField {
+    const ALL: [HudField; 5] = [
+        HudField::Score,
+        HudField::Coins,
+        HudField::World,
+        HudField::Time,
+        HudField::Lives,
+    ];
+}
+
+fn spawn_hud(mut commands: Commands) {
+    commands
+        .hud_root()
+        .insert(StateScoped(Screen::Playing))
+        .with_children(|children| {
+            for field in HudField::ALL {
+                children.text("").insert(field);
+            }
+        });
+}
+
+fn update_hud(
+    score: Res<Score>,
+    coins: Res<Coins>,
+    lives: Res<Lives>,
+    level_time: Res<LevelTime>,
+    gs: Res<GameState>,
+    mut text_q: Query<(&HudField, &mut Text)>,
+) {
+    for (field, mut text) in &mut text_q {
+        let value = match field {
+            HudField::Score => format!("SCORE {:06}", score.0),
+            HudField::Coins => format!("COINS {:02}", coins.0),
+            HudField::World => format!("WORLD {}", gs.current_level.name().to_uppercase()),
+            HudField::Time => format!("TIME {:03}", level_time.remaining_secs()),
+            HudField::Lives => format!("LIVES {}", lives.0),
+        };
+        // Only touch the text when it changes, to spare its layout.
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+}
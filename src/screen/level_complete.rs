@@ -0,0 +1,70 @@
+//! Shown once the player reaches the flag pole of a level.
+
+use bevy::prelude::*;
+
+use super::Screen;
+use crate::{
+    game::{assets::Levels, entities::ReachedCheckpoint, score::Score, GameState},
+    ui::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<LevelCompleteAction>();
+    app.add_systems(OnEnter(Screen::LevelComplete), enter_level_complete);
+    app.add_systems(
+        Update,
+        handle_level_complete_action.run_if(in_state(Screen::LevelComplete)),
+    );
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+enum LevelCompleteAction {
+    Continue,
+}
+
+fn enter_level_complete(
+    mut commands: Commands,
+    gs: Res<GameState>,
+    levels: Res<Levels>,
+    score: Res<Score>,
+) {
+    let world = gs.current_level.name().to_uppercase();
+    // After the last level, continuing goes back to the title screen.
+    let next = if levels.next(&gs.current_level).is_some() {
+        "Next Level"
+    } else {
+        "Title"
+    };
+    commands
+        .ui_root()
+        .insert(StateScoped(Screen::LevelComplete))
+        .with_children(|children| {
+            children.header(format!("World {} Complete", world));
+            children.label(format!("SCORE {:06}", score.0));
+            children.button(next).insert(LevelCompleteAction::Continue);
+        });
+}
+
+fn handle_level_complete_action(
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut gs: ResMut<GameState>,
+    levels: Res<Levels>,
+    mut reached: ResMut<ReachedCheckpoint>,
+    mut button_query: InteractionQuery<&LevelCompleteAction>,
+) {
+    for (interaction, action) in &mut button_query {
+        if matches!(interaction, Interaction::Pressed) {
+            match action {
+                LevelCompleteAction::Continue => match levels.next(&gs.current_level) {
+                    Some(next) => {
+                        gs.current_level = next;
+                        *reached = ReachedCheckpoint::default();
+                        next_screen.set(Screen::Playing);
+                    }
+                    None => next_screen.set(Screen::Title),
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! Lists every level of the levels folder, sub-levels and debug levels
+//! included, to start a game at.
+
+use bevy::{prelude::*, ui::Val::*};
+
+use super::Screen;
+use crate::{
+    game::{
+        assets::{LevelKey, Levels},
+        NewGame,
+    },
+    ui::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<LevelSelectAction>();
+    app.add_systems(OnEnter(Screen::LevelSelect), enter_level_select);
+    app.add_systems(
+        Update,
+        handle_level_select_action.run_if(in_state(Screen::LevelSelect)),
+    );
+}
+
+#[derive(Component, Debug, Clone, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+enum LevelSelectAction {
+    Play(LevelKey),
+    Back,
+}
+
+fn enter_level_select(mut commands: Commands, levels: Res<Levels>) {
+    commands
+        .ui_root()
+        .insert(StateScoped(Screen::LevelSelect))
+        .with_children(|children| {
+            children.header("Levels");
+            // Too many levels for a single column.
+            children
+                .spawn((
+                    Name::new("Level Grid"),
+                    NodeBundle {
+                        style: Style {
+                            width: Percent(80.0),
+                            flex_wrap: FlexWrap::Wrap,
+                            justify_content: JustifyContent::Center,
+                            row_gap: Px(10.0),
+                            column_gap: Px(10.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                ))
+                .with_children(|children| {
+                    for key in levels.keys() {
+                        children
+                            .button(key.name())
+                            .insert(LevelSelectAction::Play(key));
+                    }
+                });
+            children.button("Back").insert(LevelSelectAction::Back);
+        });
+}
+
+fn handle_level_select_action(
+    mut commands: Commands,
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut button_query: InteractionQuery<&LevelSelectAction>,
+) {
+    for (interaction, action) in &mut button_query {
+        if matches!(interaction, Interaction::Pressed) {
+            match action {
+                LevelSelectAction::Play(key) => {
+                    commands.trigger(NewGame(key.clone()));
+                    next_screen.set(Screen::Playing);
+                }
+                LevelSelectAction::Back => next_screen.set(Screen::Title),
+            }
+        }
+    }
+}
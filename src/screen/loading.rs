@@ -2,7 +2,7 @@ use bevy::prelude::*;
 
 use super::Screen;
 use crate::{
-    game::assets::{HandleMap, LevelKey, PatternKey, TextureKey},
+    game::assets::{HandleMap, Levels, PatternSheets, SpriteSheets, TextureKey},
     ui::prelude::*,
 };
 
@@ -26,16 +26,16 @@ fn enter_loading(mut commands: Commands) {
 fn all_assets_loaded(
     asset_server: Res<AssetServer>,
     texture_handles: Res<HandleMap<TextureKey>>,
-    level_handles: Res<HandleMap<LevelKey>>,
-    // sprite_handles: Res<HandleMap<SpriteKey>>,
-    pattern_handles: Res<HandleMap<PatternKey>>,
+    levels: Res<Levels>,
+    sprite_sheets: Res<SpriteSheets>,
+    pattern_sheets: Res<PatternSheets>,
 ) -> bool {
     texture_handles.all_loaded(&asset_server)
-        && level_handles.all_loaded(&asset_server)
-        // && sprite_handles.all_loaded(&asset_server)
-        && pattern_handles.all_loaded(&asset_server)
+        && levels.all_loaded(&asset_server)
+        && sprite_sheets.all_loaded(&asset_server)
+        && pattern_sheets.all_loaded(&asset_server)
 }
 
 fn continue_to_title(mut next_screen: ResMut<NextState<Screen>>) {
-    next_screen.set(Screen::Playing);
+    next_screen.set(Screen::Title);
 }
@@ -1,15 +1,34 @@
 //! The game's main screen states and transitions between them.
 
+mod game_over;
+mod hud;
+mod level_complete;
+mod level_select;
 mod loading;
+mod options;
+mod pause;
 mod playing;
+mod title;
 
 use bevy::prelude::*;
 
 pub(super) fn plugin(app: &mut App) {
     app.init_state::<Screen>();
     app.enable_state_scoped_entities::<Screen>();
+    app.add_sub_state::<Pause>();
+    app.enable_state_scoped_entities::<Pause>();
 
-    app.add_plugins((loading::plugin, playing::plugin));
+    app.add_plugins((
+        game_over::plugin,
+        hud::plugin,
+        level_complete::plugin,
+        level_select::plugin,
+        loading::plugin,
+        options::plugin,
+        pause::plugin,
+        playing::plugin,
+        title::plugin,
+    ));
 }
 
 /// The game's main screen states.
@@ -17,5 +36,19 @@ pub(super) fn plugin(app: &mut App) {
 pub enum Screen {
     #[default]
     Loading,
+    Title,
+    Options,
+    LevelSelect,
     Playing,
+    LevelComplete,
+    GameOver,
+}
+
+/// Whether the game is paused, only while playing.
+#[derive(SubStates, Debug, Hash, PartialEq, Eq, Clone, Default)]
+#[source(Screen = Screen::Playing)]
+pub enum Pause {
+    #[default]
+    Running,
+    Paused,
 }
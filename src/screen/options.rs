@@ -0,0 +1,61 @@
+//! Settings reached from the title screen.
+
+use bevy::{prelude::*, window::WindowMode};
+
+use super::Screen;
+use crate::{game::SettingsState, ui::prelude::*};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<OptionsAction>();
+    app.add_systems(OnEnter(Screen::Options), enter_options);
+    app.add_systems(
+        Update,
+        handle_options_action.run_if(in_state(Screen::Options)),
+    );
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+enum OptionsAction {
+    ToggleFullscreen,
+    Back,
+}
+
+fn enter_options(mut commands: Commands) {
+    commands
+        .ui_root()
+        .insert(StateScoped(Screen::Options))
+        .with_children(|children| {
+            children.header("Options");
+            children
+                .button("Fullscreen")
+                .insert(OptionsAction::ToggleFullscreen);
+            children.button("Back").insert(OptionsAction::Back);
+        });
+}
+
+fn handle_options_action(
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut settings: ResMut<SettingsState>,
+    mut window_q: Query<&mut Window>,
+    mut button_query: InteractionQuery<&OptionsAction>,
+) {
+    for (interaction, action) in &mut button_query {
+        if matches!(interaction, Interaction::Pressed) {
+            match action {
+                OptionsAction::ToggleFullscreen => {
+                    for mut window in &mut window_q {
+                        // The window may start fullscreen, whatever the settings say.
+                        settings.is_fullscreen = window.mode == WindowMode::Windowed;
+                        window.mode = if settings.is_fullscreen {
+                            WindowMode::BorderlessFullscreen
+                        } else {
+                            WindowMode::Windowed
+                        };
+                    }
+                }
+                OptionsAction::Back => next_screen.set(Screen::Title),
+            }
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//! The overlay shown while the game is paused. Pausing freezes virtual
+//! time, which stops the fixed timestep and every animation with it.
+
+use bevy::prelude::*;
+
+use super::{Pause, Screen};
+use crate::ui::prelude::*;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<PauseAction>();
+    app.add_systems(OnEnter(Pause::Paused), enter_pause);
+    app.add_systems(OnExit(Pause::Paused), exit_pause);
+    app.add_systems(
+        Update,
+        (
+            toggle_pause.run_if(in_state(Screen::Playing)),
+            handle_pause_action.run_if(in_state(Pause::Paused)),
+        ),
+    );
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+enum PauseAction {
+    Resume,
+    Quit,
+}
+
+fn enter_pause(mut commands: Commands, mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+    commands
+        .ui_root()
+        .insert(StateScoped(Pause::Paused))
+        .with_children(|children| {
+            children.header("Paused");
+            children.button("Resume").insert(PauseAction::Resume);
+            children.button("Quit").insert(PauseAction::Quit);
+        });
+}
+
+fn exit_pause(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+fn toggle_pause(
+    input: Res<ButtonInput<KeyCode>>,
+    pause: Res<State<Pause>>,
+    mut next_pause: ResMut<NextState<Pause>>,
+) {
+    if input.just_pressed(KeyCode::Escape) {
+        next_pause.set(match pause.get() {
+            Pause::Running => Pause::Paused,
+            Pause::Paused => Pause::Running,
+        });
+    }
+}
+
+fn handle_pause_action(
+    mut next_pause: ResMut<NextState<Pause>>,
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut button_query: InteractionQuery<&PauseAction>,
+) {
+    for (interaction, action) in &mut button_query {
+        if matches!(interaction, Interaction::Pressed) {
+            match action {
+                PauseAction::Resume => next_pause.set(Pause::Running),
+                PauseAction::Quit => next_screen.set(Screen::Title),
+            }
+        }
+    }
+}
@@ -1,15 +1,51 @@
 use bevy::prelude::*;
 
 use super::Screen;
-use crate::{game::spawn::level::SpawnLevel, AppSet};
+use crate::{
+    game::{
+        entities::{Lives, PlayerDied},
+        level_time::LevelTime,
+        spawn::level::{ChangeLevel, SpawnLevel},
+        GameState,
+    },
+    AppSet,
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(
         OnEnter(Screen::Playing),
         enter_playing.in_set(AppSet::TickTimers),
     );
+    app.add_systems(
+        Update,
+        respawn_or_game_over
+            .in_set(AppSet::Update)
+            .run_if(in_state(Screen::Playing)),
+    );
 }
 
-fn enter_playing(mut commands: Commands) {
+fn enter_playing(mut commands: Commands, mut level_time: ResMut<LevelTime>) {
+    level_time.reset();
     commands.trigger(SpawnLevel);
 }
+
+/// Restarts the level once the player is done dying, or ends the game when
+/// no life is left.
+fn respawn_or_game_over(
+    mut commands: Commands,
+    mut died_events: EventReader<PlayerDied>,
+    lives: Res<Lives>,
+    gs: Res<GameState>,
+    mut level_time: ResMut<LevelTime>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    if died_events.read().last().is_none() {
+        return;
+    }
+    if lives.0 == 0 {
+        next_screen.set(Screen::GameOver);
+    } else {
+        level_time.reset();
+        commands.trigger(ChangeLevel(gs.current_level.clone()));
+    }
+}
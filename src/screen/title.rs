@@ -0,0 +1,55 @@
+//! The title screen the game starts on.
+
+use bevy::prelude::*;
+
+use super::Screen;
+use crate::{
+    game::{GameState, NewGame},
+    ui::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<TitleAction>();
+    app.add_systems(OnEnter(Screen::Title), enter_title);
+    app.add_systems(Update, handle_title_action.run_if(in_state(Screen::Title)));
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+enum TitleAction {
+    Start,
+    LevelSelect,
+    Options,
+}
+
+fn enter_title(mut commands: Commands) {
+    commands
+        .ui_root()
+        .insert(StateScoped(Screen::Title))
+        .with_children(|children| {
+            children.header("Mario");
+            children.button("Start").insert(TitleAction::Start);
+            children.button("Levels").insert(TitleAction::LevelSelect);
+            children.button("Options").insert(TitleAction::Options);
+        });
+}
+
+fn handle_title_action(
+    mut commands: Commands,
+    gs: Res<GameState>,
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut button_query: InteractionQuery<&TitleAction>,
+) {
+    for (interaction, action) in &mut button_query {
+        if matches!(interaction, Interaction::Pressed) {
+            match action {
+                TitleAction::Start => {
+                    commands.trigger(NewGame(gs.start_level.clone()));
+                    next_screen.set(Screen::Playing);
+                }
+                TitleAction::LevelSelect => next_screen.set(Screen::LevelSelect),
+                TitleAction::Options => next_screen.set(Screen::Options),
+            }
+        }
+    }
+}
@@ -14,6 +14,10 @@ pub trait Widgets {
 
     /// Spawn a simple text label.
     fn label(&mut self, text: impl Into<String>) -> EntityCommands;
+
+    /// Spawn a bare text sized to its content. Unlike [`Widgets::label`], the
+    /// text is on the returned entity, so it can be updated in place.
+    fn text(&mut self, text: impl Into<String>) -> EntityCommands;
 }
 
 impl<T: Spawn> Widgets for T {
@@ -112,6 +116,20 @@ impl<T: Spawn> Widgets for T {
         });
         entity
     }
+
+    fn text(&mut self, text: impl Into<String>) -> EntityCommands {
+        self.spawn((
+            Name::new("Text"),
+            TextBundle::from_section(
+                text,
+                TextStyle {
+                    font_size: 32.0,
+                    color: LABEL_TEXT,
+                    ..default()
+                },
+            ),
+        ))
+    }
 }
 
 /// An extension trait for spawning UI containers.
@@ -119,6 +137,10 @@ pub trait Containers {
     /// Spawns a root node that covers the full screen
     /// and centers its content horizontally and vertically.
     fn ui_root(&mut self) -> EntityCommands;
+
+    /// Spawns a root node along the top of the screen
+    /// that spreads its content evenly across it.
+    fn hud_root(&mut self) -> EntityCommands;
 }
 
 impl Containers for Commands<'_, '_> {
@@ -140,6 +162,23 @@ impl Containers for Commands<'_, '_> {
             },
         ))
     }
+
+    fn hud_root(&mut self) -> EntityCommands {
+        self.spawn((
+            Name::new("HUD Root"),
+            NodeBundle {
+                style: Style {
+                    width: Percent(100.0),
+                    justify_content: JustifyContent::SpaceEvenly,
+                    align_items: AlignItems::Center,
+                    padding: UiRect::vertical(Px(20.0)),
+                    position_type: PositionType::Absolute,
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+    }
 }
 
 /// An internal trait for types that can spawn entities.
@@ -0,0 +1,59 @@
+//! Plays committed input tapes through the headless simulation.
+
+use std::path::{Path, PathBuf};
+
+use bevy::math::Vec2;
+use mario::{config::TILE_SIZE, game::movement::InputTape, headless::Simulation};
+
+/// Steps run after a tape, for the player to come to rest.
+const SETTLE_STEPS: u32 = 30;
+
+fn manifest_path(path: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join(path)
+}
+
+/// Plays the tape at `tape` on the level at `level`, returning where the
+/// player started and where it ended.
+fn play(level: &str, tape: &str) -> (Vec2, Vec2) {
+    let mut sim = Simulation::new(manifest_path("assets"));
+    sim.load_level(level).unwrap();
+    let start = sim.player_pos().expect("level spawns a player");
+
+    sim.play_tape(InputTape::load(manifest_path(tape)).unwrap());
+    while !sim.tape_finished() {
+        sim.step(1);
+    }
+    sim.step(SETTLE_STEPS);
+    (start, sim.player_pos().expect("player is still alive"))
+}
+
+#[test]
+fn walking_right_on_1_1_moves_the_player_right() {
+    let (start, end) = play(
+        "levels/1-1.level.json",
+        "tests/tapes/1-1-walk-right.tape.json",
+    );
+
+    assert!(
+        end.x > start.x + 2. * TILE_SIZE as f32,
+        "player went from {} to {}",
+        start,
+        end
+    );
+    // Still standing on the ground, well before the first pit.
+    assert!(end.y > 0., "player fell to {}", end);
+}
+
+#[test]
+fn tapes_replay_identically() {
+    let first = play(
+        "levels/1-1.level.json",
+        "tests/tapes/1-1-walk-right.tape.json",
+    );
+    let second = play(
+        "levels/1-1.level.json",
+        "tests/tapes/1-1-walk-right.tape.json",
+    );
+
+    assert_eq!(first, second);
+}